@@ -1,7 +1,15 @@
 #![feature(test)]
 extern crate test;
 extern crate phf;
-extern crate bit_vec;
+extern crate rand;
+extern crate unicode_normalization;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 pub mod tiny_trie;
-pub mod gridiron;
\ No newline at end of file
+pub mod gridiron;
+pub mod readcross;
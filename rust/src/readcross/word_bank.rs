@@ -1,26 +1,142 @@
-use ::tiny_trie::packed::{PackedTrie};
+use std::borrow::Cow;
+use std::collections::LinkedList;
+use std::ops::RangeBounds;
+use rand::Rng;
+use ::tiny_trie::glob::{length_bounds, Glob, ANY_RUN};
+use ::tiny_trie::normalize::Normalizer;
+use ::tiny_trie::packed::{assert_send_sync, PackedTrie, DEFAULT_WILDCARD};
+use ::tiny_trie::pattern::{CharSet, Pattern};
 
 
 /// Structure to contain an encoded DAWG with words of a fixed length.
+///
+/// An index can also hold the same words spelled backwards. Patterns that
+/// fix the end of the word but not the start, like `****ING`, are then
+/// searched from the end, instead of fanning out over every first letter.
 pub struct WordBankIndex {
     valence: usize,
     all_words: LinkedList<String>,
     all_wild_pattern: String,
-    trie: PackedTrie,
+    trie: PackedTrie<'static>,
+    reversed: Option<PackedTrie<'static>>,
 }
 
 impl WordBankIndex {
     pub fn new(valence: usize, trie_data: &str) -> WordBankIndex {
         let trie = PackedTrie::from(trie_data);
-        let all_wild_pattern = String::from("*");
+        let all_wild_pattern = "*".repeat(valence);
         let all_words = trie.search(&all_wild_pattern);
         WordBankIndex {
             valence,
             trie,
             all_wild_pattern,
             all_words,
+            reversed: None,
         }
     }
+
+    /// Attach the trie of the index's words spelled backwards, as produced
+    /// by `TrieBuilder::reversed`.
+    pub fn set_reversed(&mut self, trie_data: &str) -> &mut WordBankIndex {
+        let mut reversed = PackedTrie::from(trie_data);
+        reversed.set_normalizer(self.trie.normalizer().cloned());
+        self.reversed = Some(reversed);
+        self
+    }
+
+    /// The trie of reversed words, if there is one.
+    pub fn reversed(&self) -> Option<&PackedTrie<'static>> {
+        self.reversed.as_ref()
+    }
+
+    /// Set or clear the normalizer of both tries.
+    fn set_normalizer(&mut self, normalizer: Option<Normalizer>) {
+        if let Some(ref mut reversed) = self.reversed {
+            reversed.set_normalizer(normalizer.clone());
+        }
+        self.trie.set_normalizer(normalizer);
+    }
+
+    /// The length of every word in the index.
+    pub fn valence(&self) -> usize {
+        self.valence
+    }
+
+    /// The underlying trie.
+    pub fn trie(&self) -> &PackedTrie<'static> {
+        &self.trie
+    }
+
+    /// Find all words matching the pattern. Uses '*' for wildcard matching.
+    ///
+    /// The order of the words depends on which trie answers the query.
+    pub fn search(&self, pattern: &str) -> LinkedList<String> {
+        // Optimize special case to match everything.
+        if pattern == self.all_wild_pattern {
+            return self.all_words.clone();
+        }
+        match self.plan(pattern) {
+            Some((reversed, backwards)) => reversed.search_pattern(&backwards)
+                .into_iter()
+                .map(|word| word.chars().rev().collect())
+                .collect(),
+            None => self.trie.search(pattern),
+        }
+    }
+
+    /// Test if any word matches the pattern. Uses '*' for wildcard matching.
+    pub fn test(&self, pattern: &str) -> bool {
+        if pattern == self.all_wild_pattern {
+            return !self.all_words.is_empty();
+        }
+        match self.plan(pattern) {
+            Some((reversed, backwards)) => reversed.test_pattern(&backwards),
+            None => self.trie.test(pattern),
+        }
+    }
+
+    /// Decide which way to search for a pattern.
+    ///
+    /// The letters a pattern fixes before its first wildcard prune the
+    /// search from the very first level, so the direction with the longer
+    /// run of them is searched. Returns the reversed trie with the pattern
+    /// spelled backwards if that direction wins, or `None` to search the
+    /// index's own trie.
+    fn plan(&self, pattern: &str) -> Option<(&PackedTrie<'static>, Pattern)> {
+        let reversed = self.reversed.as_ref()?;
+        // Normalize first, since a digraph rule could change the length of a
+        // wildcard-free run. Patterns are used as given, so it's only done
+        // once.
+        let pattern = reversed.needle_pattern(pattern, DEFAULT_WILDCARD);
+        let fixed = |set: &&CharSet| **set != CharSet::Any;
+        let forwards = pattern.sets().iter().take_while(fixed).count();
+        let backwards = pattern.sets().iter().rev().take_while(fixed).count();
+        if backwards <= forwards {
+            return None;
+        }
+        Some((reversed, Pattern::new(pattern.sets().iter().rev().cloned().collect())))
+    }
+
+    /// Find the IDs of all words matching the pattern, in ascending order.
+    /// Uses '*' for wildcard matching.
+    ///
+    /// IDs run densely from 0 to the number of words in the index, so a
+    /// slot's candidates can be kept as a bitset (see `PackedTrie::rank`).
+    pub fn search_ids(&self, pattern: &str) -> Vec<u32> {
+        self.trie.search_ids(pattern)
+    }
+
+    /// Find the ID of a word, if it is in the index.
+    pub fn rank(&self, word: &str) -> Option<u32> {
+        self.trie.rank(word)
+    }
+
+    /// Find the word with the given ID.
+    ///
+    /// Panics if there is no word with the ID.
+    pub fn unrank(&self, id: u32) -> String {
+        self.trie.unrank(id)
+    }
 }
 
 
@@ -33,7 +149,8 @@ const INIT_IDX_CAPACITY: usize = 25;
 /// Structure to contain a list of words optimized for fast querying and
 /// efficient storage.
 pub struct WordBank {
-    indexes: Vec<WordBankIndex>,
+    indexes: Vec<Option<WordBankIndex>>,
+    normalizer: Option<Normalizer>,
 }
 
 
@@ -43,23 +160,437 @@ impl WordBank {
     pub fn new() -> WordBank {
         WordBank {
             indexes: Vec::with_capacity(INIT_IDX_CAPACITY),
+            normalizer: None,
         }
     }
 
+    /// Normalize patterns and letters passed to the search methods, for
+    /// word lists built with the same normalizer.
+    ///
+    /// Patterns are normalized before their length picks an index, so e.g.
+    /// a digraph rule makes "LLAMA" a 4-letter word.
+    pub fn set_normalizer(&mut self, normalizer: Normalizer) -> &mut WordBank {
+        for idx in self.indexes.iter_mut().flatten() {
+            idx.set_normalizer(Some(normalizer.clone()));
+        }
+        self.normalizer = Some(normalizer);
+        self
+    }
+
     /// Add a word list for the given valence.
     ///
     /// The trie should be given in its encoded binary form. The word list
     /// should contain words of a fixed length (e.g., all 3-letter words).
-    pub fn set_index(&self, valence: usize, trie_data: &str) -> &WordBank {
-        let idx = WordBankIndex::new(valence, trie_data);
+    pub fn set_index(&mut self, valence: usize, trie_data: &str) -> &mut WordBank {
+        let mut idx = WordBankIndex::new(valence, trie_data);
+        idx.set_normalizer(self.normalizer.clone());
         // Allocate more space as necessary. The initial size of the vector
         // should be chosen so that this happens rarely (if ever).
-        let cap = self.indexes.capacity();
-        if valence >= cap {
-            self.indexes.reserve(valence - (cap - 1));
+        if valence >= self.indexes.len() {
+            self.indexes.resize_with(valence + 1, || None);
         }
-        self.indexes[valence] = idx;
+        self.indexes[valence] = Some(idx);
+        self
+    }
+
+    /// Attach the trie of reversed words to the index for the given valence
+    /// (see `WordBankIndex::set_reversed`).
+    ///
+    /// Panics if there is no index for the valence.
+    pub fn set_reversed_index(&mut self, valence: usize, trie_data: &str) -> &mut WordBank {
+        match self.indexes.get_mut(valence).and_then(|idx| idx.as_mut()) {
+            Some(idx) => idx.set_reversed(trie_data),
+            None => panic!("No index for valence {}.", valence),
+        };
         self
     }
 
-}
\ No newline at end of file
+    /// Get the index for words of the given length, if there is one.
+    pub fn get_index(&self, valence: usize) -> Option<&WordBankIndex> {
+        self.indexes.get(valence).and_then(|idx| idx.as_ref())
+    }
+
+    /// Find all words matching the pattern. Uses '*' for wildcard matching.
+    pub fn search(&self, pattern: &str) -> LinkedList<String> {
+        match self.get_index(self.len_of(pattern)) {
+            Some(idx) => idx.search(pattern),
+            None => LinkedList::new(),
+        }
+    }
+
+    /// Test if any word matches the pattern. Uses '*' for wildcard matching.
+    pub fn test(&self, pattern: &str) -> bool {
+        self.get_index(self.len_of(pattern))
+            .is_some_and(|idx| idx.test(pattern))
+    }
+
+    /// Pick up to `n` distinct words matching the pattern uniformly at
+    /// random, in random order. Uses '*' for wildcard matching.
+    pub fn sample<R: Rng + ?Sized>(&self, pattern: &str, n: usize, rng: &mut R) -> Vec<String> {
+        match self.get_index(self.len_of(pattern)) {
+            Some(idx) => idx.trie.sample(pattern, n, rng),
+            None => Vec::new(),
+        }
+    }
+
+    /// Find all words using every one of the given letters plus exactly
+    /// `blanks` more, searching the index for the resulting word length.
+    pub fn anagrams(&self, letters: &str, blanks: usize) -> Vec<String> {
+        match self.get_index(self.len_of(letters) + blanks) {
+            Some(idx) => idx.trie.anagrams(letters, blanks),
+            None => Vec::new(),
+        }
+    }
+
+    /// Lazily iterate over the words matching the glob whose lengths are in
+    /// the given range, from every index that can hold them.
+    ///
+    /// Uses '*' to match any one char and '%' to match any run of chars (see
+    /// `PackedTrie::search_glob`). Shorter words come first, and words of
+    /// the same length in trie order.
+    pub fn search_glob<'a, R: RangeBounds<usize>>(&'a self, glob: &str, lengths: R) -> impl Iterator<Item = String> + 'a {
+        let (min_len, max_len) = length_bounds(&lengths);
+        // A rejected glob leaves no index in range, so nothing is found.
+        let normalized = self.normalize_pattern(glob, &[DEFAULT_WILDCARD, ANY_RUN]);
+        let (min_len, max_len) = match Glob::parse(&normalized) {
+            Ok(parsed) => (min_len.max(parsed.min_len()),
+                           parsed.max_len().map_or(max_len, |len| len.min(max_len))),
+            Err(_) => (1, 0),
+        };
+
+        let glob = glob.to_string();
+        self.indexes.iter().flatten()
+            .filter(move |idx| min_len <= idx.valence && idx.valence <= max_len)
+            .flat_map(move |idx| idx.trie.search_glob(&glob, idx.valence..=idx.valence))
+    }
+
+    /// Find every way to split the text into words from the indexes, fewest
+    /// pieces first.
+    ///
+    /// Segmentations with the same number of pieces are ordered by the
+    /// lengths of their pieces, shortest first. The number of segmentations
+    /// can grow exponentially with the length of the text.
+    pub fn segmentations(&self, text: &str) -> Vec<Vec<String>> {
+        let text = self.normalize(text);
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset)
+            .chain(Some(text.len()))
+            .collect();
+
+        // Lengths of the words starting at each position, in length order.
+        // Each index holds words of one length, so it adds at most one.
+        let words: Vec<Vec<usize>> = (0..chars.len())
+            .map(|start| {
+                let rest = &text[offsets[start]..];
+                self.indexes.iter().flatten()
+                    .filter(|idx| start + idx.valence <= chars.len())
+                    .filter(|idx| idx.trie.prefix_lengths(rest).contains(&idx.valence))
+                    .map(|idx| idx.valence)
+                    .collect()
+            })
+            .collect();
+
+        // Which positions the rest of the text can be split from, so the
+        // enumeration never walks into a dead end.
+        let mut splits = vec![false; chars.len() + 1];
+        splits[chars.len()] = true;
+        for start in (0..chars.len()).rev() {
+            splits[start] = words[start].iter().any(|&len| splits[start + len]);
+        }
+
+        // Empty text has no words to split into.
+        let mut found = Vec::new();
+        if !chars.is_empty() && splits[0] {
+            let mut pieces = Vec::new();
+            segment(&text, &offsets, &words, &splits, 0, &mut pieces, &mut found);
+        }
+        found.sort_by_key(|pieces| pieces.len());
+        found
+    }
+
+    /// Number of chars in the pattern once normalized.
+    fn len_of(&self, pattern: &str) -> usize {
+        self.normalize_pattern(pattern, &[DEFAULT_WILDCARD]).chars().count()
+    }
+
+    /// Normalize text with the bank's normalizer, if it has one.
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.normalizer {
+            Some(ref normalizer) => Cow::Owned(normalizer.normalize(text)),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Normalize a pattern with the bank's normalizer, if it has one,
+    /// leaving the given special chars alone (see `Normalizer::normalize_runs`).
+    fn normalize_pattern<'a>(&self, pattern: &'a str, specials: &[char]) -> Cow<'a, str> {
+        match self.normalizer {
+            Some(ref normalizer) => {
+                let mut normalized = String::new();
+                for (run, end) in normalizer.normalize_runs(pattern, specials) {
+                    normalized.push_str(&run);
+                    normalized.extend(end);
+                }
+                Cow::Owned(normalized)
+            },
+            None => Cow::Borrowed(pattern),
+        }
+    }
+
+}
+
+
+/// Collect the segmentations of the text from the given position, with the
+/// pieces so far (see `WordBank::segmentations`).
+fn segment(text: &str,
+           offsets: &[usize],
+           words: &[Vec<usize>],
+           splits: &[bool],
+           start: usize,
+           pieces: &mut Vec<String>,
+           found: &mut Vec<Vec<String>>) {
+    if start == words.len() {
+        found.push(pieces.clone());
+        return;
+    }
+    for &len in &words[start] {
+        if splits[start + len] {
+            pieces.push(text[offsets[start]..offsets[start + len]].to_string());
+            segment(text, offsets, words, splits, start + len, pieces, found);
+            pieces.pop();
+        }
+    }
+}
+
+
+// Word banks are read-only once their indexes are set, so they can be shared
+// across threads like the tries they hold.
+const _: fn() = assert_send_sync::<WordBankIndex>;
+const _: fn() = assert_send_sync::<WordBank>;
+
+
+impl Default for WordBank {
+    fn default() -> WordBank {
+        WordBank::new()
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::fixtures::{BRODA_5, BRODA_6, BRODA_7, BRODA_8, BRODA_9};
+
+    fn fixture() -> WordBank {
+        let mut three = TrieBuilder::new();
+        three.extend(vec!["ARE", "EAR", "ERA", "TEA"]);
+        let mut four = TrieBuilder::new();
+        four.extend(vec!["RATE", "TEAR", "TARE", "AREA"]);
+
+        let mut wb = WordBank::new();
+        wb.set_index(3, &three.encode()).set_index(4, &four.encode());
+        wb
+    }
+
+    #[test]
+    fn test_word_bank_search() {
+        let wb = fixture();
+        assert_eq!(wb.search("***").len(), 4);
+        assert_eq!(wb.search("T***").len(), 2);
+        assert_eq!(wb.search("*****").len(), 0);
+        assert!(wb.test("E*A"));
+        assert!(!wb.test("E*T"));
+        assert!(!wb.test("**"));
+        assert!(wb.get_index(4).is_some());
+        assert!(wb.get_index(40).is_none());
+    }
+
+    #[test]
+    fn test_word_bank_index_ids() {
+        let wb = fixture();
+        let idx = wb.get_index(4).unwrap();
+        assert_eq!(idx.search_ids("T***"), vec![2, 3]);
+        assert_eq!(idx.search_ids("****"), vec![0, 1, 2, 3]);
+        assert_eq!(idx.rank("RATE"), Some(1));
+        assert_eq!(idx.rank("RATS"), None);
+        assert_eq!(idx.unrank(3), "TEAR");
+    }
+
+    #[test]
+    fn test_word_bank_normalizer() {
+        let spanish = Normalizer::spanish().rule("LL", "Ŀ");
+        let mut four = TrieBuilder::new();
+        four.set_normalizer(spanish.clone());
+        four.extend(vec!["llama", "calle", "caña"]);
+
+        let mut wb = WordBank::new();
+        wb.set_normalizer(spanish).set_index(4, &four.encode());
+        assert!(wb.test("Llama"));
+        assert!(wb.test("CALLE"));
+        assert!(wb.test("caña"));
+        assert!(!wb.test("cana"));
+        assert_eq!(wb.search("ca**").len(), 2);
+        assert_eq!(wb.search("*a**").len(), 3);
+        assert!(wb.get_index(5).is_none());
+    }
+
+    #[test]
+    fn test_word_bank_sample() {
+        let wb = fixture();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut picks = wb.sample("T***", 5, &mut rng);
+        picks.sort();
+        assert_eq!(picks, vec!["TARE", "TEAR"]);
+        assert_eq!(wb.sample("***", 2, &mut rng).len(), 2);
+        assert!(wb.sample("*****", 2, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_word_bank_reversed() {
+        let mut four = TrieBuilder::new();
+        four.extend(vec!["RATE", "TEAR", "TARE", "AREA"]);
+        let mut wb = fixture();
+        wb.set_reversed_index(4, &four.reversed().encode());
+
+        let idx = wb.get_index(4).unwrap();
+        assert!(idx.reversed().is_some());
+        assert!(wb.get_index(3).unwrap().reversed().is_none());
+        assert_eq!(wb.search("**RE").into_iter().collect::<Vec<String>>(), vec!["TARE"]);
+        assert_eq!(wb.search("***A").into_iter().collect::<Vec<String>>(), vec!["AREA"]);
+        assert!(wb.test("*EAR"));
+        assert!(!wb.test("**AT"));
+        assert_eq!(wb.search("T***").len(), 2);
+    }
+
+    // Both directions find the same words in a real list.
+    #[test]
+    fn test_word_bank_reversed_dist_file() {
+        let words: Vec<String> = PackedTrie::from(BRODA_5.trim()).search_pfx_iter("").collect();
+        let mut five = TrieBuilder::new();
+        five.extend(words.iter().map(|w| w.as_str()));
+        let mut wb = WordBank::new();
+        wb.set_index(5, BRODA_5.trim());
+        let mut with_reversed = WordBank::new();
+        with_reversed.set_index(5, BRODA_5.trim())
+            .set_reversed_index(5, &five.reversed().encode());
+
+        for pattern in &["***ED", "**ING", "*A**S", "S***Y", "**E**", "****Z"] {
+            let mut forward: Vec<String> = wb.search(pattern).into_iter().collect();
+            let mut planned: Vec<String> = with_reversed.search(pattern).into_iter().collect();
+            forward.sort();
+            planned.sort();
+            assert_eq!(planned, forward, "{}", pattern);
+            assert_eq!(with_reversed.test(pattern), wb.test(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_word_bank_segmentations() {
+        let mut two = TrieBuilder::new();
+        two.extend(vec!["IT", "AW", "AT", "TO"]);
+        let mut three = TrieBuilder::new();
+        three.extend(vec!["ITS", "RAP", "SAW", "ONE"]);
+        let mut four = TrieBuilder::new();
+        four.extend(vec!["WRAP", "ATON", "TONE"]);
+        let mut five = TrieBuilder::new();
+        five.extend(vec!["ATONE"]);
+        let mut wb = WordBank::new();
+        wb.set_index(2, &two.encode())
+            .set_index(3, &three.encode())
+            .set_index(4, &four.encode())
+            .set_index(5, &five.encode());
+
+        assert_eq!(wb.segmentations("ITSAWRAP"), vec![vec!["IT", "SAW", "RAP"], vec!["ITS", "AW", "RAP"]]);
+        assert_eq!(wb.segmentations("ITSAWRAPTO").len(), 2);
+        assert_eq!(wb.segmentations("ATONE"), vec![vec!["ATONE"], vec!["AT", "ONE"]]);
+        assert_eq!(wb.segmentations("ITATONE"), vec![vec!["IT", "ATONE"], vec!["IT", "AT", "ONE"]]);
+        assert!(wb.segmentations("ITSA").is_empty());
+        assert!(wb.segmentations("XYZ").is_empty());
+        assert!(wb.segmentations("").is_empty());
+    }
+
+    #[test]
+    fn test_word_bank_segmentations_normalized() {
+        let mut three = TrieBuilder::new();
+        three.set_normalizer(Normalizer::french());
+        three.extend(vec!["été", "mer"]);
+        let mut wb = WordBank::new();
+        wb.set_normalizer(Normalizer::french()).set_index(3, &three.encode());
+        assert_eq!(wb.segmentations("Été mer"), Vec::<Vec<String>>::new());
+        assert_eq!(wb.segmentations("étémer"), vec![vec!["ETE", "MER"]]);
+    }
+
+    #[test]
+    fn test_word_bank_search_glob() {
+        let wb = fixture();
+        assert_eq!(wb.search_glob("%E%", ..).collect::<Vec<String>>(),
+                   vec!["ARE", "EAR", "ERA", "TEA", "AREA", "RATE", "TARE", "TEAR"]);
+        assert_eq!(wb.search_glob("%E%", 4..).collect::<Vec<String>>(), vec!["AREA", "RATE", "TARE", "TEAR"]);
+        assert_eq!(wb.search_glob("T%", ..=3).collect::<Vec<String>>(), vec!["TEA"]);
+        assert_eq!(wb.search_glob("%A", ..).collect::<Vec<String>>(), vec!["ERA", "TEA", "AREA"]);
+        assert_eq!(wb.search_glob("*A*", ..).collect::<Vec<String>>(), vec!["EAR"]);
+        assert_eq!(wb.search_glob("%", 5..).count(), 0);
+        assert_eq!(wb.search_glob(&"*".repeat(200), ..).count(), 0);
+    }
+
+    // Words of length 5-9 that start with QU and contain Z.
+    #[test]
+    fn test_word_bank_search_glob_dist_files() {
+        let lists = [
+            (5, BRODA_5),
+            (6, BRODA_6),
+            (7, BRODA_7),
+            (8, BRODA_8),
+            (9, BRODA_9),
+        ];
+        let mut wb = WordBank::new();
+        let mut expected = Vec::new();
+        for &(valence, data) in &lists {
+            wb.set_index(valence, data.trim());
+            let mut pattern = String::from("QU");
+            pattern.extend(std::iter::repeat_n('*', valence - 2));
+            expected.extend(wb.get_index(valence).unwrap().trie().search_iter(&pattern)
+                .filter(|word| word.contains('Z')));
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(wb.search_glob("QU%Z%", 5..=9).collect::<Vec<String>>(), expected);
+        assert!(wb.search_glob("QU%Z%", 5..=9).all(|word| (5..=9).contains(&word.len())));
+    }
+
+    #[test]
+    fn test_word_bank_anagrams() {
+        let wb = fixture();
+        assert_eq!(wb.anagrams("AER", 0), vec!["ARE", "EAR", "ERA"]);
+        assert_eq!(wb.anagrams("AER", 1), vec!["AREA", "RATE", "TARE", "TEAR"]);
+        assert_eq!(wb.anagrams("TAE", 0), vec!["TEA"]);
+        assert!(wb.anagrams("AER", 4).is_empty());
+    }
+
+
+    // Benchmarks
+
+    #[bench]
+    fn bench_word_bank_leading_wildcard(b: &mut Bencher) {
+        let mut wb = WordBank::new();
+        wb.set_index(5, BRODA_5.trim());
+        b.iter(|| wb.search("***ED"));
+    }
+
+    #[bench]
+    fn bench_word_bank_leading_wildcard_reversed(b: &mut Bencher) {
+        let words: Vec<String> = PackedTrie::from(BRODA_5.trim()).search_pfx_iter("").collect();
+        let mut five = TrieBuilder::new();
+        five.extend(words.iter().map(|w| w.as_str()));
+        let mut wb = WordBank::new();
+        wb.set_index(5, BRODA_5.trim()).set_reversed_index(5, &five.reversed().encode());
+        b.iter(|| wb.search("***ED"));
+    }
+
+}
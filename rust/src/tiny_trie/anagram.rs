@@ -0,0 +1,132 @@
+use ::tiny_trie::packed::PackedTrie;
+
+
+// Anagram search -----------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Find all words that use every one of the given letters plus exactly
+    /// `blanks` additional letters of any kind.
+    ///
+    /// Letters form a multiset, so repeated letters must be repeated in the
+    /// word. With no blanks this finds the exact anagrams of `letters`.
+    /// Words are returned in trie order.
+    pub fn anagrams(&self, letters: &str, blanks: usize) -> Vec<String> {
+        let letters = self.normalize(letters);
+        let matches = Vec::new();
+        let root = match self.root() {
+            Some(root) => root,
+            None => return matches,
+        };
+
+        // Tally the letters by char table index. A letter that isn't in the
+        // table can never be consumed, so nothing can match.
+        let mut tiles = vec![0usize; self.char_count() + 1];
+        for c in letters.chars() {
+            match self.char_index(c) {
+                Some(idx) => tiles[idx as usize] += 1,
+                None => return matches,
+            }
+        }
+
+        let mut walk = AnagramWalk {
+            trie: self,
+            tiles,
+            blanks,
+            remaining: letters.chars().count() + blanks,
+            memo: String::new(),
+            matches,
+        };
+        walk.level(root);
+        walk.matches
+    }
+
+}
+
+
+/// State of a single anagram search over a trie.
+struct AnagramWalk<'a> {
+    trie: &'a PackedTrie<'a>,
+    /// Unused letters, by char table index.
+    tiles: Vec<usize>,
+    /// Unused blanks.
+    blanks: usize,
+    /// Number of letters and blanks left to place.
+    remaining: usize,
+    /// Chars on the path to the current level.
+    memo: String,
+    matches: Vec<String>,
+}
+
+
+impl<'a> AnagramWalk<'a> {
+
+    /// Collect anagrams from the level at the given pointer.
+    fn level(&mut self, pointer: usize) {
+        let mut word_ptr = pointer;
+        loop {
+            let word = self.trie.read_node(word_ptr);
+            let idx = word.char_idx as usize;
+            if idx == 0 {
+                if self.remaining == 0 {
+                    self.matches.push(self.memo.clone());
+                }
+            } else if self.remaining > 0 {
+                // Always spend a real letter before a blank. A blank can stand
+                // in for anything, so this never loses a match, and it keeps
+                // each word from being found twice.
+                let use_tile = self.tiles[idx] > 0;
+                if use_tile || self.blanks > 0 {
+                    if use_tile {
+                        self.tiles[idx] -= 1;
+                    } else {
+                        self.blanks -= 1;
+                    }
+                    self.remaining -= 1;
+                    self.memo.push(self.trie.char_at(word.char_idx));
+
+                    self.level(word.next);
+
+                    self.memo.pop();
+                    self.remaining += 1;
+                    if use_tile {
+                        self.tiles[idx] += 1;
+                    } else {
+                        self.blanks += 1;
+                    }
+                }
+            }
+            if word.last {
+                break;
+            }
+            word_ptr += 1;
+        }
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use ::tiny_trie::builder::TrieBuilder;
+
+    #[test]
+    fn test_anagrams() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["ARE", "EAR", "ERA", "RAE", "REAR", "RARE", "TEAR", "AREA", "ERR"]);
+        let pt = builder.build();
+
+        assert_eq!(pt.anagrams("EAR", 0), vec!["ARE", "EAR", "ERA", "RAE"]);
+        assert_eq!(pt.anagrams("RAE", 1), vec!["AREA", "RARE", "REAR", "TEAR"]);
+        assert_eq!(pt.anagrams("RRAE", 0), vec!["RARE", "REAR"]);
+        assert_eq!(pt.anagrams("R", 2), vec!["ARE", "EAR", "ERA", "ERR", "RAE"]);
+        assert_eq!(pt.anagrams("", 3).len(), 5);
+        assert!(pt.anagrams("EAR", 2).is_empty());
+        assert!(pt.anagrams("EAX", 1).is_empty());
+        assert!(TrieBuilder::new().build().anagrams("EAR", 0).is_empty());
+    }
+
+}
@@ -1,17 +1,100 @@
 use ::phf;
+use ::tiny_trie::error::TrieLoadError;
 
 
 include!(concat!(env!("OUT_DIR"), "/base64_const.rs"));
 
 
 /// Lookup the integer value of a base64 character.
+///
+/// Returns `None` if the character is not in the Base64 alphabet.
 #[inline]
-pub fn char_to_int<'a>(c: &'a char) -> &u32 {
-    BASE64_CHAR_TO_INT.get(c).unwrap()
+pub fn char_to_int(c: char) -> Option<u32> {
+    BASE64_CHAR_TO_INT.get(&c).cloned()
 }
 
 /// Lookup the base64 character value of an integer.
 #[inline]
-pub fn int_to_char<'a>(i: &u32) -> &'a char {
-    BASE64_INT_TO_CHAR.get(i).unwrap()
-}
\ No newline at end of file
+pub fn int_to_char(i: u32) -> char {
+    *BASE64_INT_TO_CHAR.get(&i).unwrap()
+}
+
+/// Resolve a Base64 symbol to its value, failing with its offset if the
+/// symbol is not in the alphabet.
+#[inline]
+pub fn decode_symbol(c: char, offset: usize) -> Result<u32, TrieLoadError> {
+    char_to_int(c).ok_or(TrieLoadError::InvalidBase64Symbol { symbol: c, offset })
+}
+
+/// Unpack Base64 content into bytes, six bits per symbol, most-significant
+/// bit first. The final byte is zero-padded.
+///
+/// The `start` offset of the slice within the full encoding is only used to
+/// report the position of invalid symbols.
+pub fn decode(base64: &str, start: usize) -> Result<Vec<u8>, TrieLoadError> {
+    let mut bytes = Vec::with_capacity((6 * base64.len()).div_ceil(8));
+    let mut acc: u32 = 0;
+    let mut pending: u32 = 0;
+    for (pos, c) in base64.char_indices() {
+        acc = (acc << 6) | decode_symbol(c, start + pos)?;
+        pending += 6;
+        if pending >= 8 {
+            pending -= 8;
+            bytes.push((acc >> pending) as u8);
+        }
+    }
+    if pending > 0 {
+        bytes.push((acc << (8 - pending)) as u8);
+    }
+    Ok(bytes)
+}
+
+/// Pack the first `bits` bits of the bytes as Base64, most-significant bit
+/// first. The final symbol is zero-padded.
+///
+/// Panics if the bytes hold fewer than `bits` bits.
+pub fn encode(bytes: &[u8], bits: usize) -> String {
+    assert!(bits <= 8 * bytes.len(), "Cannot encode {} bits from {} bytes.", bits, bytes.len());
+    (0..bits.div_ceil(6))
+        .map(|i| {
+            let mut val = 0u32;
+            for bit in 6 * i..6 * i + 6 {
+                val <<= 1;
+                if bit < bits && (bytes[bit / 8] >> (7 - bit % 8)) & 0x1 == 0x1 {
+                    val |= 0x1;
+                }
+            }
+            int_to_char(val)
+        })
+        .collect()
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // For reference, "foo+" encodes the binary:
+        // 0111 1110 1000 1010 0011 1110
+        assert_eq!(decode("foo+", 0).unwrap(), vec![0x7e, 0x8a, 0x3e]);
+        // "fo" is padded out to a whole byte.
+        assert_eq!(decode("fo", 0).unwrap(), vec![0x7e, 0x80]);
+        assert_eq!(decode("", 0).unwrap(), Vec::<u8>::new());
+        assert_eq!(decode("fo!", 10).err(),
+                   Some(TrieLoadError::InvalidBase64Symbol { symbol: '!', offset: 12 }));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(&[0x7e, 0x8a, 0x3e], 24), "foo+");
+        assert_eq!(encode(&[0x7e, 0x80], 12), "fo");
+        assert_eq!(encode(&[0x7e, 0xff], 10), "fs");
+        assert_eq!(encode(&[], 0), "");
+    }
+
+}
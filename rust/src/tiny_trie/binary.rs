@@ -0,0 +1,109 @@
+//! Conversion between the Base64 and raw binary trie encodings.
+//!
+//! Both encodings share the same header fields and body layout. The Base64
+//! form is what `TrieBuilder` and the Typescript `tiny-trie` produce, and
+//! is what the word lists in `data/dist` are shipped as. The raw binary
+//! form stores the body as plain bytes so `PackedTrie::from_bytes` can read
+//! it in place, e.g. from a memory-mapped file, without decoding anything.
+//!
+//! The binary layout is:
+//!
+//! | Bytes          | Field                                        |
+//! |----------------|----------------------------------------------|
+//! | 0..4           | Magic number `TTRI`                          |
+//! | 4..6           | Header width in bytes, big-endian `u16`      |
+//! | 6..8           | Version, big-endian `u16`                    |
+//! | 8..12          | Pointer offset, big-endian `i32`             |
+//! | 12             | Char width in bits                           |
+//! | 13             | Pointer width in bits                        |
+//! | 14             | Score width in bits (version 1 only)         |
+//! | 14/15..header  | Char table, UTF-8, padded with `\0`          |
+//! | header..       | Body, most-significant bit first             |
+//!
+//! The score width byte is the binary form of the Base64 header's
+//! `SCORE_WIDTH_FIELD`, so the char table starts at byte 14 in version 0
+//! and at byte 15 in version 1.
+//!
+//! The header and body are each padded to a multiple of 8 bytes, so the
+//! body can be read in place as big-endian 64-bit words. Bodies are
+//! converted up to their last whole node; the bits padding out the final
+//! Base64 char or word are not carried over.
+use ::tiny_trie::base64;
+use ::tiny_trie::error::TrieLoadError;
+use ::tiny_trie::header::Header;
+
+
+/// Convert a Base64-encoded trie to the raw binary format.
+pub fn base64_to_binary(packed: &str) -> Result<Vec<u8>, TrieLoadError> {
+    let (header, body_start) = Header::from_base64(packed)?;
+    let mut body = base64::decode(&packed[body_start..], body_start)?;
+    body.resize(whole_nodes(&header, 8 * body.len()).div_ceil(64) * 8, 0);
+
+    let mut encoded = header.to_binary();
+    encoded.extend_from_slice(&body);
+    Ok(encoded)
+}
+
+
+/// Convert a trie in the raw binary format to its Base64 encoding.
+pub fn binary_to_base64(bytes: &[u8]) -> Result<String, TrieLoadError> {
+    let (header, body_start) = Header::from_binary(bytes)?;
+    let body = &bytes[body_start..];
+
+    let mut encoded = header.to_base64();
+    encoded.push_str(&base64::encode(body, whole_nodes(&header, 8 * body.len())));
+    Ok(encoded)
+}
+
+
+/// Round a body width in bits down to its last whole node.
+#[inline]
+fn whole_nodes(header: &Header, bits: usize) -> usize {
+    bits - bits % header.word_width()
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tiny_trie::fixtures::BRODA_3;
+    use ::tiny_trie::packed::PackedTrie;
+
+    #[test]
+    fn test_base64_to_binary() {
+        let binary = base64_to_binary("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+        assert_eq!(&binary[..4], b"TTRI");
+        // 24 header bytes, then 10 whole 6-bit nodes in one word.
+        assert_eq!(binary.len(), 32);
+        assert_eq!(binary_to_base64(&binary).unwrap(), "BAAAAABAwIfboarzKTbjds1FDB");
+    }
+
+    #[test]
+    fn test_round_trip_dist_file() {
+        let packed = BRODA_3.trim();
+        let binary = base64_to_binary(packed).unwrap();
+        assert!(binary.len() < packed.len());
+
+        let from_str = PackedTrie::from(packed);
+        let from_bytes = PackedTrie::from_bytes(&binary).unwrap();
+        assert_eq!(from_bytes.search("***"), from_str.search("***"));
+
+        let again = binary_to_base64(&binary).unwrap();
+        assert_eq!(base64_to_binary(&again).unwrap(), binary);
+        assert_eq!(PackedTrie::from(&again).search("***"), from_str.search("***"));
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        assert_eq!(base64_to_binary("BAAAA").err(),
+                   Some(TrieLoadError::TruncatedHeader { len: 5, expected: 10 }));
+        assert_eq!(base64_to_binary("BAAAAABAwIfboarzKTb~ds1FDB").err(),
+                   Some(TrieLoadError::InvalidBase64Symbol { symbol: '~', offset: 19 }));
+        assert_eq!(binary_to_base64(b"BAAAAABAwIfboarz").err(),
+                   Some(TrieLoadError::InvalidMagic));
+    }
+
+}
@@ -0,0 +1,49 @@
+use ::tiny_trie::base64::{int_to_char};
+
+
+/// Append-only bit buffer used to produce the packed trie encodings.
+///
+/// Bits are stored most-significant first, matching the order in which the
+/// reader pulls fields out of the Base64 stream.
+pub struct BitWriter {
+    bits: Vec<bool>,
+}
+
+
+impl BitWriter {
+
+    /// Create an empty writer.
+    pub fn new() -> BitWriter {
+        BitWriter {
+            bits: Vec::new(),
+        }
+    }
+
+    /// Append the low `width` bits of `value`.
+    ///
+    /// Panics if the value does not fit in the given width.
+    pub fn write(&mut self, value: u32, width: usize) {
+        assert!(width >= 32 || value >> width == 0,
+                "Value {} does not fit in {} bits.", value, width);
+        for i in (0..width).rev() {
+            self.bits.push(i < 32 && (value >> i) & 0x1 == 0x1);
+        }
+    }
+
+    /// Render the buffer as Base64, zero-padding the final character.
+    pub fn to_base64(&self) -> String {
+        self.bits.chunks(6)
+            .map(|chunk| {
+                let mut val = 0u32;
+                for i in 0..6 {
+                    val <<= 1;
+                    if chunk.get(i) == Some(&true) {
+                        val |= 0x1;
+                    }
+                }
+                int_to_char(val)
+            })
+            .collect()
+    }
+
+}
@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use ::tiny_trie::bit_writer::BitWriter;
+use ::tiny_trie::constants::{OFFSET_VAL_FIELD,
+                             SCORED_VERSION,
+                             TERMINAL,
+                             VERSION};
+use ::tiny_trie::header::Header;
+use ::tiny_trie::normalize::Normalizer;
+use ::tiny_trie::packed::PackedTrie;
+
+
+/// Mutable trie node used while collecting words.
+#[derive(Default)]
+struct BuildNode {
+    terminal: bool,
+    score: u32,
+    children: BTreeMap<char, usize>,
+}
+
+
+/// Node of the minimized DAWG. Children are kept in char order; equal nodes
+/// have equal signatures and are stored once. Nodes only merge when the
+/// words below them have the same scores.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct DawgNode {
+    terminal: bool,
+    score: u32,
+    children: Vec<(char, usize)>,
+}
+
+
+/// Builder that collects words and encodes them as a packed DAWG.
+///
+/// The output uses the same Base64 header and body layout that `PackedTrie`
+/// (and the Typescript `tiny-trie` reader) consume. Common suffixes are
+/// merged, so the encoding is usually much smaller than the naive trie.
+///
+/// Chars are laid out in sorted order, both in the char table and within
+/// each level, so traversing the packed trie depth-first yields words in
+/// lexicographic order.
+///
+/// Words added with `insert_scored` carry a score, and make the builder emit
+/// the scored (version 1) format. Otherwise the output is version 0.
+///
+/// With a normalizer, words are normalized as they are inserted, and tries
+/// from `build` normalize their queries the same way.
+pub struct TrieBuilder {
+    nodes: Vec<BuildNode>,
+    scored: bool,
+    normalizer: Option<Normalizer>,
+}
+
+
+impl TrieBuilder {
+
+    /// Create an empty builder.
+    pub fn new() -> TrieBuilder {
+        TrieBuilder {
+            nodes: vec![BuildNode::default()],
+            scored: false,
+            normalizer: None,
+        }
+    }
+
+    /// Normalize words inserted from now on.
+    pub fn set_normalizer(&mut self, normalizer: Normalizer) -> &mut TrieBuilder {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Add a word to the trie. Duplicates are ignored.
+    ///
+    /// Panics if the word contains the reserved terminal char.
+    pub fn insert(&mut self, word: &str) -> &mut TrieBuilder {
+        let node = self.insert_node(word);
+        self.nodes[node].terminal = true;
+        self
+    }
+
+    /// Add a word to the trie with a score. Adding a word again replaces
+    /// its score.
+    ///
+    /// Panics if the word contains the reserved terminal char.
+    pub fn insert_scored(&mut self, word: &str, score: u32) -> &mut TrieBuilder {
+        let node = self.insert_node(word);
+        self.nodes[node].terminal = true;
+        self.nodes[node].score = score;
+        self.scored = true;
+        self
+    }
+
+    /// Add the path for a word, returning the node it ends at.
+    fn insert_node(&mut self, word: &str) -> usize {
+        let normalized;
+        let word = match self.normalizer {
+            Some(ref normalizer) => {
+                normalized = normalizer.normalize(word);
+                &normalized[..]
+            },
+            None => word,
+        };
+        assert!(!word.contains(TERMINAL), "Words may not contain the terminal char.");
+        let mut node = 0;
+        for c in word.chars() {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&next) => next,
+                None => {
+                    let next = self.nodes.len();
+                    self.nodes.push(BuildNode::default());
+                    self.nodes[node].children.insert(c, next);
+                    next
+                }
+            };
+        }
+        node
+    }
+
+    /// Freeze the collected words into a `PackedTrie`.
+    pub fn build(&self) -> PackedTrie<'static> {
+        let mut trie = PackedTrie::from(&self.encode());
+        trie.set_normalizer(self.normalizer.clone());
+        trie
+    }
+
+    /// Create a builder holding every word spelled backwards, with the same
+    /// scores and normalizer.
+    ///
+    /// A trie of reversed words is a suffix index for the original list,
+    /// for patterns that fix the end of the word but not the start (see
+    /// `WordBankIndex::set_reversed`).
+    pub fn reversed(&self) -> TrieBuilder {
+        let mut reversed = TrieBuilder::new();
+        reversed.scored = self.scored;
+        let mut stack = vec![(0, String::new())];
+        while let Some((node, word)) = stack.pop() {
+            if self.nodes[node].terminal {
+                let backwards: String = word.chars().rev().collect();
+                let end = reversed.insert_node(&backwards);
+                reversed.nodes[end].terminal = true;
+                reversed.nodes[end].score = self.nodes[node].score;
+            }
+            for (&c, &child) in &self.nodes[node].children {
+                let mut next = word.clone();
+                next.push(c);
+                stack.push((child, next));
+            }
+        }
+        // Words were normalized on the way in, so only later inserts need it.
+        reversed.normalizer = self.normalizer.clone();
+        reversed
+    }
+
+    /// Minimize the trie and encode it in the packed Base64 format.
+    pub fn encode(&self) -> String {
+        let (dawg, root) = self.minimize();
+
+        // Lay out levels in BFS order. Each DAWG node becomes one level with
+        // a word per child, plus a leading terminal word if a word ends here.
+        let mut positions: Vec<Option<usize>> = vec![None; dawg.len()];
+        let mut order: Vec<usize> = Vec::with_capacity(dawg.len());
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        queue.push_back(root);
+        positions[root] = Some(0);
+        let mut next_pos = level_len(&dawg[root]);
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for &(_, child) in &dawg[id].children {
+                if positions[child].is_none() {
+                    positions[child] = Some(next_pos);
+                    next_pos += level_len(&dawg[child]);
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        // Compute relative jumps for every edge. The header offset is the
+        // smallest jump so stored pointers are never negative.
+        let mut jumps: Vec<i64> = Vec::new();
+        let mut ptr = 0;
+        for &id in &order {
+            let node = &dawg[id];
+            ptr += node.terminal as usize;
+            for &(_, child) in &node.children {
+                jumps.push(positions[child].unwrap() as i64 - ptr as i64);
+                ptr += 1;
+            }
+        }
+        let offset = jumps.iter().cloned().min().unwrap_or(0);
+        let max_ptr = jumps.iter().map(|j| (j - offset) as u32).max().unwrap_or(0);
+
+        // Every node scores as well as the best word below it, so readers
+        // can prune low-scoring subtrees. Children come before their parents
+        // in the DAWG, so one pass in order sees every child first.
+        let mut best: Vec<u32> = Vec::with_capacity(dawg.len());
+        for node in &dawg {
+            let score = node.children.iter()
+                .map(|&(_, child)| best[child])
+                .fold(node.score, u32::max);
+            best.push(score);
+        }
+
+        // Collect the char table in sorted order. Index 0 is the terminal.
+        let chars: BTreeSet<char> = dawg.iter()
+            .flat_map(|node| node.children.iter().map(|&(c, _)| c))
+            .collect();
+        let char_table: String = chars.iter().collect();
+        let char_index: HashMap<char, u32> = chars.iter()
+            .enumerate()
+            .map(|(i, &c)| (c, i as u32 + 1))
+            .collect();
+
+        let char_width = bit_width(chars.len() as u32);
+        let ptr_width = bit_width(max_ptr);
+        let score_width = if self.scored { bit_width(best[root]).max(1) } else { 0 };
+        assert!(char_width + ptr_width < 32, "Trie is too large to encode.");
+        assert!(offset.abs() < (1 << OFFSET_VAL_FIELD), "Trie is too large to encode.");
+
+        let header = Header {
+            version: if self.scored { SCORED_VERSION } else { VERSION },
+            offset: offset as i32,
+            char_width,
+            ptr_width,
+            score_width,
+            char_table: &char_table,
+        };
+
+        let mut body = BitWriter::new();
+        let mut jumps = jumps.iter();
+        for &id in &order {
+            let node = &dawg[id];
+            let n = level_len(node);
+            let mut i = 0;
+            if node.terminal {
+                i += 1;
+                write_word(&mut body, &header, node.score, 0, 0, i == n);
+            }
+            for &(c, child) in &node.children {
+                i += 1;
+                let jump = (jumps.next().unwrap() - offset) as u32;
+                write_word(&mut body, &header, best[child], char_index[&c], jump, i == n);
+            }
+        }
+
+        let mut encoded = header.to_base64();
+        encoded.push_str(&body.to_base64());
+        encoded
+    }
+
+    /// Merge equivalent subtrees, returning the DAWG nodes and the root id.
+    fn minimize(&self) -> (Vec<DawgNode>, usize) {
+        let mut dawg: Vec<DawgNode> = Vec::new();
+        let mut registry: HashMap<DawgNode, usize> = HashMap::new();
+        let mut canonical: Vec<usize> = vec![0; self.nodes.len()];
+
+        // Children are always created after their parents, so walking the
+        // arena backwards visits every child before its parent.
+        for id in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[id];
+            let dawg_node = DawgNode {
+                terminal: node.terminal,
+                score: node.score,
+                children: node.children.iter()
+                    .map(|(&c, &child)| (c, canonical[child]))
+                    .collect(),
+            };
+            canonical[id] = match registry.get(&dawg_node) {
+                Some(&existing) => existing,
+                None => {
+                    let next = dawg.len();
+                    dawg.push(dawg_node.clone());
+                    registry.insert(dawg_node, next);
+                    next
+                }
+            };
+        }
+
+        (dawg, canonical[0])
+    }
+
+}
+
+
+impl Default for TrieBuilder {
+    fn default() -> TrieBuilder {
+        TrieBuilder::new()
+    }
+}
+
+
+impl<'a> Extend<&'a str> for TrieBuilder {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, words: I) {
+        for word in words {
+            self.insert(word);
+        }
+    }
+}
+
+
+impl<'a> Extend<(&'a str, u32)> for TrieBuilder {
+    fn extend<I: IntoIterator<Item = (&'a str, u32)>>(&mut self, words: I) {
+        for (word, score) in words {
+            self.insert_scored(word, score);
+        }
+    }
+}
+
+
+
+// Private support methods --------------------------------------------------
+
+/// Number of words a DAWG node occupies in the packed body.
+#[inline]
+fn level_len(node: &DawgNode) -> usize {
+    node.terminal as usize + node.children.len()
+}
+
+
+/// Number of bits needed to represent the given value.
+#[inline]
+fn bit_width(val: u32) -> u32 {
+    32 - val.leading_zeros()
+}
+
+
+/// Append a single node to the body, using the field widths in the header.
+#[inline]
+fn write_word(w: &mut BitWriter, header: &Header, score: u32, char_idx: u32, ptr: u32, last: bool) {
+    w.write(score, header.score_width as usize);
+    w.write(char_idx, header.char_width as usize);
+    w.write(ptr, header.ptr_width as usize);
+    w.write(last as u32, 1);
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tiny_trie::fixtures::BRODA_3;
+
+    // Encoding is stable for a known word list.
+    #[test]
+    fn test_encode() {
+        let mut builder = TrieBuilder::new();
+        builder.insert("foo").insert("bar").insert("baz");
+        assert_eq!(builder.encode(), "BAAAAABAwIabforzSbLlszhB");
+    }
+
+    // Built tries load and answer queries.
+    #[test]
+    fn test_build() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["foo", "bar", "baz", "bar"]);
+        let pt = builder.build();
+
+        assert!(pt.test("foo"));
+        assert!(pt.test("bar"));
+        assert!(pt.test("baz"));
+        assert!(!pt.test("bao"));
+        assert_eq!(pt.search("***").len(), 3);
+        assert_eq!(pt.search("ba*").len(), 2);
+    }
+
+    // Words of mixed length, including prefixes of other words.
+    #[test]
+    fn test_build_mixed_lengths() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["a", "ab", "abc", "b", "cab"]);
+        let pt = builder.build();
+
+        assert!(pt.test("a"));
+        assert!(pt.test("ab"));
+        assert!(pt.test("abc"));
+        assert!(!pt.test("ca"));
+        assert_eq!(pt.search("**").len(), 1);
+        assert_eq!(pt.search_pfx("a").len(), 3);
+    }
+
+    // Scored words produce a version 1 trie that keeps their scores.
+    #[test]
+    fn test_build_scored() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec![("bar", 50), ("baz", 10), ("foo", 30), ("bar", 60)]);
+        let pt = builder.build();
+        assert!(pt.has_scores());
+        assert_eq!(pt.score("bar"), Some(60));
+        assert_eq!(pt.score("baz"), Some(10));
+        assert_eq!(pt.score("bat"), None);
+        assert_eq!(pt.search("***").len(), 3);
+    }
+
+    // Suffixes are only shared between words with the same scores.
+    #[test]
+    fn test_build_scored_minimizes() {
+        let mut same = TrieBuilder::new();
+        same.extend(vec![("cat", 5), ("hat", 5)]);
+        let mut different = TrieBuilder::new();
+        different.extend(vec![("cat", 5), ("hat", 6)]);
+        assert!(same.encode().len() < different.encode().len());
+
+        let pt = different.build();
+        assert_eq!(pt.search_scored("*at", 0),
+                   vec![(String::from("cat"), 5), (String::from("hat"), 6)]);
+    }
+
+    // An empty builder produces a trie with no words.
+    #[test]
+    fn test_build_empty() {
+        let pt = TrieBuilder::new().build();
+        assert!(!pt.test("a"));
+        assert!(pt.search_pfx("").is_empty());
+    }
+
+    // Reversing keeps every word and its score.
+    #[test]
+    fn test_reversed() {
+        let mut builder = TrieBuilder::new();
+        builder.insert_scored("bake", 2).insert_scored("baked", 7).insert_scored("cake", 4);
+        let pt = builder.reversed().build();
+        assert_eq!(pt.search_pfx_iter("").collect::<Vec<String>>(), vec!["dekab", "ekab", "ekac"]);
+        assert_eq!(pt.score("dekab"), Some(7));
+
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["ab", "cd"]);
+        let pt = builder.reversed().build();
+        assert!(!pt.has_scores());
+        assert_eq!(pt.search_pfx_iter("").collect::<Vec<String>>(), vec!["ba", "dc"]);
+    }
+
+    // Re-encoding a wordlist produced by the JS encoder preserves its words.
+    #[test]
+    fn test_reencode_js_wordlist() {
+        let original = PackedTrie::from(BRODA_3);
+        let mut words: Vec<String> = original.search("***").into_iter().collect();
+
+        let mut builder = TrieBuilder::new();
+        builder.extend(words.iter().map(|w| w.as_str()));
+        let encoded = builder.encode();
+        let rebuilt = PackedTrie::from(&encoded);
+        let mut rebuilt_words: Vec<String> = rebuilt.search("***").into_iter().collect();
+
+        words.sort();
+        rebuilt_words.sort();
+        assert_eq!(words, rebuilt_words);
+    }
+
+}
@@ -10,6 +10,10 @@ pub const TERMINUS: () = ();
 /// Header version
 pub const VERSION: u32 = 0;
 
+/// Header version with per-word score payloads. Adds the score width field
+/// after the pointer width, and a score to every node in the body.
+pub const SCORED_VERSION: u32 = 1;
+
 /// Width of header field storing entire header width (including char table).
 /// Value is given in Base64 characters (i.e., every six bits).
 pub const HEADER_WIDTH_FIELD: usize = 10;
@@ -29,6 +33,10 @@ pub const CHAR_WIDTH_FIELD: usize = 8;
 /// Width of header field representing the width of the offset pointer in a word.
 pub const POINTER_WIDTH_FIELD: usize = 8;
 
+/// Width of header field representing the width of the score in a word.
+/// Only present from `SCORED_VERSION` on.
+pub const SCORE_WIDTH_FIELD: usize = 8;
+
 
 // Constants for hard-coded body fields -------------------------------------
 
@@ -36,4 +44,16 @@ pub const POINTER_WIDTH_FIELD: usize = 8;
 pub const LAST_MASK: u32 = 0x1;
 
 /// Offset of the pointer field within a node.
-pub const PTR_SHIFT: u32 = 1;
\ No newline at end of file
+pub const PTR_SHIFT: u32 = 1;
+
+// Constants describing the raw binary format -------------------------------
+
+/// Magic number at the start of a raw binary trie.
+pub const BINARY_MAGIC: [u8; 4] = *b"TTRI";
+
+/// Width in bytes of the fixed binary header fields: the magic number, the
+/// header width (u16), version (u16), offset (i32), char width (u8) and
+/// pointer width (u8). From `SCORED_VERSION` on, a score width (u8) follows.
+/// The char table comes next as UTF-8, padded so the body starts on an
+/// 8-byte boundary.
+pub const BINARY_FIELDS_WIDTH: usize = 14;
@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::fmt;
+
+
+/// Reasons a packed trie can fail to load from its encoded form.
+///
+/// Offsets and widths are given in bytes of the encoded input unless noted
+/// otherwise. For the Base64 encoding these are also its chars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieLoadError {
+    /// The input is too short to contain the fixed-width header fields.
+    TruncatedHeader { len: usize, expected: usize },
+    /// The header declares a version this build does not know how to parse.
+    UnsupportedVersion { version: u32 },
+    /// A character outside the Base64 alphabet was found in an encoded field.
+    InvalidBase64Symbol { symbol: char, offset: usize },
+    /// The header width field points past the end of the input.
+    HeaderWidthExceedsInput { header_width: usize, len: usize },
+    /// The char table has more entries than the char width can index.
+    CharTableOverflow { entries: usize, char_width: u32 },
+    /// A node is wider than the 64 bits the reader can extract at once, or
+    /// one of its fields is wider than 32 bits.
+    WordTooWide { word_width: usize },
+    /// Binary input does not start with the expected magic number.
+    InvalidMagic,
+    /// The char table in a binary header is not valid UTF-8.
+    InvalidCharTable { offset: usize },
+}
+
+
+impl fmt::Display for TrieLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrieLoadError::TruncatedHeader { len, expected } =>
+                write!(f, "Truncated header: got {} chars, expected at least {}.", len, expected),
+            TrieLoadError::UnsupportedVersion { version } =>
+                write!(f, "Unsupported header version {}.", version),
+            TrieLoadError::InvalidBase64Symbol { symbol, offset } =>
+                write!(f, "Invalid Base64 symbol {:?} at offset {}.", symbol, offset),
+            TrieLoadError::HeaderWidthExceedsInput { header_width, len } =>
+                write!(f, "Header width {} exceeds input length {}.", header_width, len),
+            TrieLoadError::CharTableOverflow { entries, char_width } =>
+                write!(f, "Char table has {} entries, too many for a {}-bit char index.",
+                       entries, char_width),
+            TrieLoadError::WordTooWide { word_width } =>
+                write!(f, "Node width of {} bits exceeds the maximum.", word_width),
+            TrieLoadError::InvalidMagic =>
+                write!(f, "Input does not start with the binary trie magic number."),
+            TrieLoadError::InvalidCharTable { offset } =>
+                write!(f, "Char table is not valid UTF-8 at offset {}.", offset),
+        }
+    }
+}
+
+
+impl Error for TrieLoadError {}
+
+
+/// Reasons a search pattern can fail to parse.
+///
+/// Offsets are given in bytes of the pattern string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// A char class was opened but never closed.
+    UnclosedClass { offset: usize },
+    /// A char class was closed without being opened.
+    UnexpectedClassClose { offset: usize },
+}
+
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatternError::UnclosedClass { offset } =>
+                write!(f, "Unclosed char class starting at offset {}.", offset),
+            PatternError::UnexpectedClassClose { offset } =>
+                write!(f, "Unexpected end of char class at offset {}.", offset),
+        }
+    }
+}
+
+
+impl Error for PatternError {}
+
+
+/// Reasons a glob can fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobError {
+    /// The glob has more positions than its states can track, counting
+    /// consecutive runs as one.
+    TooManyPositions { positions: usize, max: usize },
+}
+
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GlobError::TooManyPositions { positions, max } =>
+                write!(f, "Glob has {} positions, more than the maximum of {}.", positions, max),
+        }
+    }
+}
+
+
+impl Error for GlobError {}
+
+
+/// Structural problems `PackedTrie::verify` can find in a trie body.
+///
+/// Nodes and levels are identified by their index in the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// A node points to a level outside the body.
+    PointerOutOfBounds { node: usize, pointer: i64 },
+    /// A level runs off the end of the body without a node flagged as last.
+    UnterminatedLevel { level: usize },
+    /// A node's char index has no entry in the char table.
+    InvalidCharIndex { node: usize, char_idx: u32 },
+    /// A node points back to a level on the path leading to it.
+    Cycle { node: usize },
+    /// A level lies more than `u16::MAX` levels below the root, so words
+    /// through it are too long to search.
+    TooDeep { level: usize },
+    /// The trie has more words than fit in a `u32` word ID.
+    TooManyWords { words: u64 },
+}
+
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::PointerOutOfBounds { node, pointer } =>
+                write!(f, "Node {} points to {}, outside the trie body.", node, pointer),
+            VerifyError::UnterminatedLevel { level } =>
+                write!(f, "Level at node {} has no last node.", level),
+            VerifyError::InvalidCharIndex { node, char_idx } =>
+                write!(f, "Node {} has char index {}, which is not in the char table.",
+                       node, char_idx),
+            VerifyError::Cycle { node } =>
+                write!(f, "Node {} points back to a level on its own path.", node),
+            VerifyError::TooDeep { level } =>
+                write!(f, "Level at node {} is more than {} levels deep.", level, u16::MAX),
+            VerifyError::TooManyWords { words } =>
+                write!(f, "Trie has {} words, more than {}.", words, u32::MAX),
+        }
+    }
+}
+
+
+impl Error for VerifyError {}
+
+
+/// Problems compiling a query against a particular trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// None of the chars allowed at a position are in the trie's char table,
+    /// so nothing can match. The first such char is given.
+    UnknownChar { c: char, position: usize },
+}
+
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::UnknownChar { c, position } =>
+                write!(f, "Char {:?} at position {} is not in the char table.", c, position),
+        }
+    }
+}
+
+
+impl Error for QueryError {}
@@ -0,0 +1,22 @@
+//! Word lists from `data/dist`, shared by the tests and benchmarks.
+
+/// Broda list of 3-letter words.
+pub const BRODA_3: &str = include_str!("../../../data/dist/broda/3.ab31070b4bfe1812e0c2622288942b25f9350b69.dawg");
+
+/// Broda list of 5-letter words.
+pub const BRODA_5: &str = include_str!("../../../data/dist/broda/5.5ff5e59ae69343ba922c3007486cdda4ee829085.dawg");
+
+/// Broda list of 6-letter words.
+pub const BRODA_6: &str = include_str!("../../../data/dist/broda/6.e7f2dd7853ac348b972bef4c945916109e662df1.dawg");
+
+/// Broda list of 7-letter words.
+pub const BRODA_7: &str = include_str!("../../../data/dist/broda/7.8b19b4f1f2936b55e49ba3a83e2f2715eaa38818.dawg");
+
+/// Broda list of 8-letter words.
+pub const BRODA_8: &str = include_str!("../../../data/dist/broda/8.125ea9b18abcd144270cb862f6c9c6cb9cab7e7e.dawg");
+
+/// Broda list of 9-letter words.
+pub const BRODA_9: &str = include_str!("../../../data/dist/broda/9.5d078d79d24616cacd39cf2624d175fbe3a85a16.dawg");
+
+/// NYT list of 7-letter words.
+pub const NYT_7: &str = include_str!("../../../data/dist/nyt16Year/7.91af0c854137645f4ec44efcc248600f9886799e.dawg");
@@ -0,0 +1,222 @@
+use ::tiny_trie::packed::PackedTrie;
+
+
+// Levenshtein automaton ----------------------------------------------------
+
+/// Levenshtein automaton for a fixed word and edit budget.
+///
+/// A state is the row of the edit distance matrix for the input consumed so
+/// far, i.e. the distance from that input to every prefix of the word.
+/// Stepping the automaton with a char computes the next row. This is the
+/// "simulated" form of the automaton; it trades a little speed per step for
+/// not having to build the DFA up front.
+struct LevenshteinAutomaton {
+    word: Vec<char>,
+    max_edits: usize,
+}
+
+
+impl LevenshteinAutomaton {
+
+    fn new(word: &str, max_edits: usize) -> LevenshteinAutomaton {
+        LevenshteinAutomaton {
+            word: word.chars().collect(),
+            max_edits,
+        }
+    }
+
+    /// State before any input is consumed.
+    fn start(&self) -> Vec<usize> {
+        (0..self.word.len() + 1).collect()
+    }
+
+    /// Advance from the given state by consuming a char.
+    fn step(&self, state: &[usize], c: char) -> Vec<usize> {
+        let mut next = Vec::with_capacity(state.len());
+        next.push(state[0] + 1);
+        for (i, &w) in self.word.iter().enumerate() {
+            let substitute = state[i] + (w != c) as usize;
+            let insert = state[i + 1] + 1;
+            let delete = next[i] + 1;
+            next.push(substitute.min(insert).min(delete));
+        }
+        next
+    }
+
+    /// Edit distance between the consumed input and the whole word.
+    #[inline]
+    fn distance(&self, state: &[usize]) -> usize {
+        state[state.len() - 1]
+    }
+
+    /// Whether the consumed input is within the edit budget of the word.
+    #[inline]
+    fn is_match(&self, state: &[usize]) -> bool {
+        self.distance(state) <= self.max_edits
+    }
+
+    /// Whether any continuation of the consumed input could still match.
+    #[inline]
+    fn can_match(&self, state: &[usize]) -> bool {
+        state.iter().any(|&d| d <= self.max_edits)
+    }
+
+}
+
+
+
+// Fuzzy search -------------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Find all words within `max_edits` Levenshtein edits of the given word.
+    ///
+    /// Results are paired with their edit distance and ranked by it, closest
+    /// first; ties are broken alphabetically.
+    pub fn search_fuzzy(&self, word: &str, max_edits: usize) -> Vec<(String, usize)> {
+        self.search_fuzzy_impl(&self.normalize(word), max_edits, None)
+    }
+
+    /// Like `search_fuzzy`, but only returns words with the same length as
+    /// the input. Use this with fixed-length tries, e.g. a `WordBankIndex`.
+    pub fn search_fuzzy_same_len(&self, word: &str, max_edits: usize) -> Vec<(String, usize)> {
+        let word = self.normalize(word);
+        self.search_fuzzy_impl(&word, max_edits, Some(word.chars().count()))
+    }
+
+    /// Walk the trie in lockstep with the automaton, pruning any branch the
+    /// automaton can no longer accept.
+    fn search_fuzzy_impl(&self,
+                         word: &str,
+                         max_edits: usize,
+                         len: Option<usize>) -> Vec<(String, usize)> {
+        let automaton = LevenshteinAutomaton::new(word, max_edits);
+        let mut walk = FuzzyWalk {
+            trie: self,
+            automaton: &automaton,
+            len,
+            memo: String::new(),
+            matches: Vec::new(),
+        };
+        if let Some(root) = self.root() {
+            walk.level(root, 0, &automaton.start());
+        }
+        let mut matches = walk.matches;
+        matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        matches
+    }
+
+}
+
+
+/// State of a single fuzzy search over a trie.
+struct FuzzyWalk<'a> {
+    trie: &'a PackedTrie<'a>,
+    automaton: &'a LevenshteinAutomaton,
+    /// Required length of matches, if any.
+    len: Option<usize>,
+    /// Chars on the path to the current level.
+    memo: String,
+    matches: Vec<(String, usize)>,
+}
+
+
+impl<'a> FuzzyWalk<'a> {
+
+    /// Collect fuzzy matches from the level at the given pointer.
+    fn level(&mut self, pointer: usize, depth: usize, state: &[usize]) {
+        let mut word_ptr = pointer;
+        loop {
+            let word = self.trie.read_node(word_ptr);
+            if word.char_idx == 0 {
+                if self.automaton.is_match(state) && self.len.is_none_or(|len| depth == len) {
+                    self.matches.push((self.memo.clone(), self.automaton.distance(state)));
+                }
+            } else if self.len.is_none_or(|len| depth < len) {
+                let c = self.trie.char_at(word.char_idx);
+                let next = self.automaton.step(state, c);
+                if self.automaton.can_match(&next) {
+                    self.memo.push(c);
+                    self.level(word.next, depth + 1, &next);
+                    self.memo.pop();
+                }
+            }
+            if word.last {
+                break;
+            }
+            word_ptr += 1;
+        }
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+
+    fn fixture() -> PackedTrie<'static> {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["BAR", "BARE", "BAZ", "BRA", "CAR", "FOO", "OBAR"]);
+        builder.build()
+    }
+
+    #[test]
+    fn test_levenshtein_automaton() {
+        let automaton = LevenshteinAutomaton::new("BAR", 1);
+        let mut state = automaton.start();
+        for c in "BAZ".chars() {
+            state = automaton.step(&state, c);
+        }
+        assert_eq!(automaton.distance(&state), 1);
+        assert!(automaton.is_match(&state));
+
+        let state = automaton.step(&automaton.step(&automaton.start(), 'X'), 'Y');
+        assert!(!automaton.can_match(&state));
+    }
+
+    #[test]
+    fn test_search_fuzzy() {
+        let pt = fixture();
+        assert_eq!(pt.search_fuzzy("BAR", 0), vec![(String::from("BAR"), 0)]);
+        assert_eq!(pt.search_fuzzy("BAR", 1),
+                   vec![(String::from("BAR"), 0),
+                        (String::from("BARE"), 1),
+                        (String::from("BAZ"), 1),
+                        (String::from("CAR"), 1),
+                        (String::from("OBAR"), 1)]);
+        assert_eq!(pt.search_fuzzy("BAR", 2).len(), 6);
+        assert!(pt.search_fuzzy("QQQ", 2).is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_same_len() {
+        let pt = fixture();
+        assert_eq!(pt.search_fuzzy_same_len("BAR", 1),
+                   vec![(String::from("BAR"), 0),
+                        (String::from("BAZ"), 1),
+                        (String::from("CAR"), 1)]);
+        assert_eq!(pt.search_fuzzy_same_len("BAR", 2),
+                   vec![(String::from("BAR"), 0),
+                        (String::from("BAZ"), 1),
+                        (String::from("CAR"), 1),
+                        (String::from("BRA"), 2)]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_empty_trie() {
+        assert!(TrieBuilder::new().build().search_fuzzy("BAR", 2).is_empty());
+    }
+
+    #[bench]
+    fn bench_search_fuzzy(b: &mut Bencher) {
+        let pt = fixture();
+        b.iter(|| pt.search_fuzzy("BAR", 2));
+    }
+
+}
@@ -0,0 +1,394 @@
+//! Variable-length glob searches.
+//!
+//! Patterns match words of one length, unless they are used as prefixes. A
+//! `Glob` can match words of any length: `*` matches any one char, `%` any
+//! run of chars (including none), and any other char, digits included,
+//! matches itself, as in plain needles (see `Pattern`). So `QU%Z%` finds
+//! words starting with `QU` that contain a `Z`. Searches also take a range
+//! of word lengths, which prunes the trie walk as well.
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+use ::tiny_trie::error::GlobError;
+use ::tiny_trie::packed::{PackedTrie, DEFAULT_WILDCARD};
+
+
+// Constants ----------------------------------------------------------------
+
+/// Matches any run of chars in a glob, including an empty one.
+pub const ANY_RUN: char = '%';
+
+
+
+// Globs --------------------------------------------------------------------
+
+/// One position of a glob.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    /// The given char.
+    Char(char),
+    /// Any one char.
+    Any,
+    /// Any run of chars.
+    Run,
+}
+
+
+/// A parsed glob, matched as a set of states: state `i` means the first `i`
+/// tokens have been matched. The state past the last token accepts.
+///
+/// States are kept as bits of a `States` word, which limits globs to
+/// `MAX_TOKENS` positions. That is far longer than any word in the lists.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+
+/// Set of glob states, one bit per state.
+pub(crate) type States = u128;
+
+
+/// Most positions a glob can have.
+pub const MAX_TOKENS: usize = States::BITS as usize - 1;
+
+
+impl Glob {
+
+    /// Parse a glob where `*` matches any one char and `%` any run of chars.
+    ///
+    /// Fails if the glob has more than `MAX_TOKENS` positions, counting
+    /// consecutive runs as one.
+    pub fn parse(glob: &str) -> Result<Glob, GlobError> {
+        let mut tokens = Vec::new();
+        for c in glob.chars() {
+            let token = match c {
+                DEFAULT_WILDCARD => Token::Any,
+                ANY_RUN if tokens.last() == Some(&Token::Run) => continue,
+                ANY_RUN => Token::Run,
+                _ => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        if tokens.len() > MAX_TOKENS {
+            return Err(GlobError::TooManyPositions { positions: tokens.len(), max: MAX_TOKENS });
+        }
+        Ok(Glob { tokens })
+    }
+
+    /// Length of the shortest word the glob can match.
+    pub fn min_len(&self) -> usize {
+        self.tokens.iter().filter(|&token| *token != Token::Run).count()
+    }
+
+    /// Length of the longest word the glob can match, or `None` if it has a
+    /// run and so no limit.
+    pub fn max_len(&self) -> Option<usize> {
+        if self.tokens.contains(&Token::Run) {
+            None
+        } else {
+            Some(self.tokens.len())
+        }
+    }
+
+    /// Test whether the glob matches a word.
+    pub fn matches(&self, word: &str) -> bool {
+        let states = word.chars().fold(self.start(), |states, c| self.step(states, c));
+        self.accepts(states)
+    }
+
+    /// States before any char has been read.
+    pub(crate) fn start(&self) -> States {
+        self.close(1)
+    }
+
+    /// States after reading the char. None are live if the glob can no
+    /// longer match.
+    pub(crate) fn step(&self, states: States, c: char) -> States {
+        let mut next = 0;
+        for (state, &token) in self.tokens.iter().enumerate() {
+            if states & (1 << state) == 0 {
+                continue;
+            }
+            match token {
+                Token::Char(want) if want != c => (),
+                Token::Char(_) | Token::Any => next |= 1 << (state + 1),
+                Token::Run => next |= 1 << state,
+            }
+        }
+        self.close(next)
+    }
+
+    /// Whether the states include the accepting one.
+    #[inline]
+    pub(crate) fn accepts(&self, states: States) -> bool {
+        states & (1 << self.tokens.len()) != 0
+    }
+
+    /// Add the states reachable by letting runs match nothing.
+    fn close(&self, mut states: States) -> States {
+        for (state, &token) in self.tokens.iter().enumerate() {
+            if states & (1 << state) != 0 && token == Token::Run {
+                states |= 1 << (state + 1);
+            }
+        }
+        states
+    }
+
+}
+
+
+impl FromStr for Glob {
+    type Err = GlobError;
+
+    fn from_str(glob: &str) -> Result<Glob, GlobError> {
+        Glob::parse(glob)
+    }
+}
+
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &token in &self.tokens {
+            match token {
+                Token::Char(c) => write!(f, "{}", c)?,
+                Token::Any => write!(f, "{}", DEFAULT_WILDCARD)?,
+                Token::Run => write!(f, "{}", ANY_RUN)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Resolve a range of lengths to its inclusive bounds.
+pub(crate) fn length_bounds<R: RangeBounds<usize>>(lengths: &R) -> (usize, usize) {
+    let min = match lengths.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let max = match lengths.end_bound() {
+        Bound::Included(&max) => max,
+        Bound::Excluded(&max) => match max.checked_sub(1) {
+            Some(max) => max,
+            // An empty range; no length fits.
+            None => return (1, 0),
+        },
+        Bound::Unbounded => usize::MAX,
+    };
+    (min, max)
+}
+
+
+
+// Glob search --------------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Lazily iterate over the words matching the glob whose lengths are in
+    /// the given range, in trie order.
+    ///
+    /// Uses '*' to match any one char and '%' to match any run of chars.
+    /// Subtrees whose words are all too short or too long are skipped. A
+    /// glob that `Glob::parse` rejects finds nothing.
+    pub fn search_glob<'b, R: RangeBounds<usize>>(&'b self, glob: &str, lengths: R) -> GlobIter<'b> {
+        let glob = match Glob::parse(&self.normalize(glob)) {
+            Ok(glob) => glob,
+            Err(_) => return GlobIter::empty(self),
+        };
+        let (min_len, max_len) = length_bounds(&lengths);
+        let max_len = glob.max_len().map_or(max_len, |len| len.min(max_len));
+
+        let mut stack = Vec::new();
+        if let Some(root) = self.root() {
+            if glob.min_len().max(min_len) <= max_len {
+                stack.push(GlobFrame { pointer: root, done: false, states: glob.start() });
+            }
+        }
+
+        GlobIter {
+            trie: self,
+            glob,
+            min_len,
+            max_len,
+            stack,
+            memo: String::new(),
+        }
+    }
+
+}
+
+
+/// Level on the DFS stack of a glob search.
+struct GlobFrame {
+    /// Index of the next node to examine in the level.
+    pointer: usize,
+    /// Whether the last node in the level has been examined.
+    done: bool,
+    /// Glob states after reading the chars on the path to the level.
+    states: States,
+}
+
+
+/// Iterator over the words matching a glob, produced by a lazy DFS.
+pub struct GlobIter<'a> {
+    trie: &'a PackedTrie<'a>,
+    glob: Glob,
+    min_len: usize,
+    max_len: usize,
+    stack: Vec<GlobFrame>,
+    memo: String,
+}
+
+
+impl<'a> GlobIter<'a> {
+
+    /// Iterator that finds nothing.
+    fn empty(trie: &'a PackedTrie<'a>) -> GlobIter<'a> {
+        GlobIter {
+            trie,
+            glob: Glob { tokens: Vec::new() },
+            min_len: 0,
+            max_len: 0,
+            stack: Vec::new(),
+            memo: String::new(),
+        }
+    }
+
+}
+
+
+impl<'a> Iterator for GlobIter<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while !self.stack.is_empty() {
+            let depth = self.stack.len() - 1;
+            let frame = &mut self.stack[depth];
+
+            // Backtrack once every node in the level has been examined.
+            if frame.done {
+                self.stack.pop();
+                self.memo.pop();
+                continue;
+            }
+
+            let node = self.trie.read_node(frame.pointer);
+            frame.pointer += 1;
+            frame.done = node.last;
+
+            if node.char_idx == 0 {
+                if depth >= self.min_len && self.glob.accepts(frame.states) {
+                    return Some(self.memo.clone());
+                }
+                continue;
+            }
+
+            // Skip the child unless some word below it has a length in range.
+            let stats = self.trie.level_stats()[node.next];
+            let below = depth + 1;
+            if stats.count == 0
+                || below + stats.min_len as usize > self.max_len
+                || below + (stats.max_len as usize) < self.min_len {
+                continue;
+            }
+
+            let c = self.trie.char_at(node.char_idx);
+            let states = self.glob.step(frame.states, c);
+            if states == 0 {
+                continue;
+            }
+            self.memo.push(c);
+            self.stack.push(GlobFrame { pointer: node.next, done: false, states });
+        }
+
+        None
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::fixtures::NYT_7;
+
+    #[test]
+    fn test_glob_matches() {
+        let glob = Glob::parse("QU%Z%").unwrap();
+        assert!(glob.matches("QUIZ"));
+        assert!(glob.matches("QUARTZ"));
+        assert!(glob.matches("QUIZZES"));
+        assert!(!glob.matches("QUITE"));
+        assert!(!glob.matches("SQUEEZE"));
+        assert_eq!(glob.min_len(), 3);
+        assert_eq!(glob.max_len(), None);
+
+        assert!(Glob::parse("%").unwrap().matches(""));
+        assert!(Glob::parse("A%%B").unwrap().matches("AB"));
+        assert_eq!(Glob::parse("A%%B").unwrap().to_string(), "A%B");
+        assert!(Glob::parse("*A*").unwrap().matches("BAT"));
+        assert!(!Glob::parse("*A*").unwrap().matches("BAAT"));
+        assert_eq!(Glob::parse("*A*").unwrap().max_len(), Some(3));
+        assert!(Glob::parse(&"*%".repeat(MAX_TOKENS / 2)).unwrap().matches(&"AB".repeat(MAX_TOKENS / 2)));
+        assert_eq!("A%%B".parse::<Glob>(), Glob::parse("A%B"));
+    }
+
+    // Globs too long to track are rejected rather than panicking, and
+    // searches for them find nothing.
+    #[test]
+    fn test_glob_too_long() {
+        let long = "*".repeat(MAX_TOKENS + 1);
+        assert_eq!(Glob::parse(&long), Err(GlobError::TooManyPositions { positions: MAX_TOKENS + 1, max: MAX_TOKENS }));
+        assert!(Glob::parse(&"%".repeat(MAX_TOKENS + 1)).is_ok());
+
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["QUIZ"]);
+        assert_eq!(builder.build().search_glob(&long, ..).count(), 0);
+    }
+
+    #[test]
+    fn test_length_bounds() {
+        assert_eq!(length_bounds(&(5..=9)), (5, 9));
+        assert_eq!(length_bounds(&(5..9)), (5, 8));
+        assert_eq!(length_bounds(&(..)), (0, usize::MAX));
+        assert_eq!(length_bounds(&(..0)), (1, 0));
+    }
+
+    #[test]
+    fn test_search_glob() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["QUIZ", "QUARTZ", "QUITE", "QUIZZES", "QUARTZES", "ZQU", "QU"]);
+        let pt = builder.build();
+
+        let all: Vec<String> = pt.search_glob("QU%Z%", ..).collect();
+        assert_eq!(all, vec!["QUARTZ", "QUARTZES", "QUIZ", "QUIZZES"]);
+        assert_eq!(pt.search_glob("QU%Z%", 5..=7).collect::<Vec<String>>(), vec!["QUARTZ", "QUIZZES"]);
+        assert_eq!(pt.search_glob("QU%", 2..3).collect::<Vec<String>>(), vec!["QU"]);
+        assert_eq!(pt.search_glob("%Z", ..).collect::<Vec<String>>(), vec!["QUARTZ", "QUIZ"]);
+        assert_eq!(pt.search_glob("****", ..).collect::<Vec<String>>(), vec!["QUIZ"]);
+        assert_eq!(pt.search_glob("%", 9..).count(), 0);
+        assert_eq!(pt.search_glob("%", ..).count(), 7);
+        assert_eq!(TrieBuilder::new().build().search_glob("%", ..).count(), 0);
+
+        // Digits are literals, as in plain needles.
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["100TH", "10TO2", "1ST", "ABBA"]);
+        let pt = builder.build();
+        assert_eq!(pt.search_glob("10%", ..).collect::<Vec<String>>(), vec!["100TH", "10TO2"]);
+        assert_eq!(pt.search_glob("%2", ..).collect::<Vec<String>>(), vec!["10TO2"]);
+        assert_eq!(pt.search_glob("1221", ..).count(), 0);
+    }
+
+    #[bench]
+    fn bench_nyt_search_glob(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.search_glob("%A%E%", ..).count());
+    }
+
+}
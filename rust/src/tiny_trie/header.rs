@@ -0,0 +1,540 @@
+use std::str;
+use ::tiny_trie::base64::decode_symbol;
+use ::tiny_trie::bit_writer::BitWriter;
+use ::tiny_trie::constants::{BINARY_FIELDS_WIDTH,
+                             BINARY_MAGIC,
+                             CHAR_WIDTH_FIELD,
+                             HEADER_WIDTH_FIELD,
+                             OFFSET_SIGN_FIELD,
+                             OFFSET_VAL_FIELD,
+                             POINTER_WIDTH_FIELD,
+                             SCORE_WIDTH_FIELD,
+                             SCORED_VERSION,
+                             TERMINAL,
+                             VERSION,
+                             VERSION_FIELD};
+use ::tiny_trie::error::TrieLoadError;
+
+
+/// Width in bits of the fields every Base64 header starts with, i.e. the
+/// fixed fields of version 0. Later versions only ever add fields.
+const BASE64_FIELDS_WIDTH: usize = HEADER_WIDTH_FIELD + VERSION_FIELD + OFFSET_SIGN_FIELD
+    + OFFSET_VAL_FIELD + CHAR_WIDTH_FIELD + POINTER_WIDTH_FIELD;
+
+
+/// Descriptor of a packed trie's header, common to every header version and
+/// to both encodings.
+///
+/// The char table is borrowed from the encoded input. Fields a version does
+/// not have take their neutral value; e.g. version 0 tries have no scores,
+/// which is the same as a score width of 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Header<'a> {
+    pub version: u32,
+    pub offset: i32,
+    pub char_width: u32,
+    pub ptr_width: u32,
+    pub score_width: u32,
+    pub char_table: &'a str,
+}
+
+
+impl<'a> Header<'a> {
+
+    /// Parse the header of the Base64 encoding. Returns the header with the
+    /// offset at which the body starts.
+    pub fn from_base64(packed: &'a str) -> Result<(Header<'a>, usize), TrieLoadError> {
+        // The fixed-width fields must all be present before anything can be
+        // read from the header.
+        let fields_char_width = BASE64_FIELDS_WIDTH.div_ceil(6);
+        if packed.len() < fields_char_width {
+            return Err(TrieLoadError::TruncatedHeader {
+                len: packed.len(),
+                expected: fields_char_width,
+            });
+        }
+
+        // Read the header width from the initial field.
+        let header_char_width = get_base64_field(packed,
+                                                 0,
+                                                 HEADER_WIDTH_FIELD)? as usize;
+
+        // Cut a slice for the header alone and work with this for the rest
+        // of the init processing.
+        let header = match packed.get(..header_char_width) {
+            Some(header) if header_char_width >= fields_char_width => header,
+            _ => return Err(TrieLoadError::HeaderWidthExceedsInput {
+                header_width: header_char_width,
+                len: packed.len(),
+            }),
+        };
+
+        // Read the version and find the decoder for the rest of the fields.
+        let version = get_base64_field(header, HEADER_WIDTH_FIELD, VERSION_FIELD)?;
+        let format = HeaderFormat::lookup(version)?;
+        if header_char_width < format.base64_width.div_ceil(6) {
+            return Err(TrieLoadError::HeaderWidthExceedsInput {
+                header_width: header_char_width,
+                len: packed.len(),
+            });
+        }
+
+        let mut descriptor = Header::empty(version);
+        (format.read_base64)(header, &mut descriptor)?;
+
+        // The rest of the header is the character table.
+        descriptor.char_table = &header[format.base64_width.div_ceil(6)..];
+
+        Ok((descriptor, header_char_width))
+    }
+
+    /// Parse the header of the raw binary format. Returns the header with the
+    /// offset at which the body starts.
+    pub fn from_binary(bytes: &'a [u8]) -> Result<(Header<'a>, usize), TrieLoadError> {
+        if bytes.len() < BINARY_FIELDS_WIDTH {
+            return Err(TrieLoadError::TruncatedHeader {
+                len: bytes.len(),
+                expected: BINARY_FIELDS_WIDTH,
+            });
+        }
+        if bytes[..4] != BINARY_MAGIC {
+            return Err(TrieLoadError::InvalidMagic);
+        }
+
+        let version = u16::from_be_bytes([bytes[6], bytes[7]]) as u32;
+        let format = HeaderFormat::lookup(version)?;
+
+        let header_width = u16::from_be_bytes([bytes[4], bytes[5]]) as usize;
+        if header_width < format.binary_width || header_width > bytes.len() {
+            return Err(TrieLoadError::HeaderWidthExceedsInput {
+                header_width,
+                len: bytes.len(),
+            });
+        }
+
+        let mut descriptor = Header::empty(version);
+        (format.read_binary)(bytes, &mut descriptor);
+
+        // The char table is padded with terminals so the body is aligned.
+        descriptor.char_table = str::from_utf8(&bytes[format.binary_width..header_width])
+            .map_err(|e| TrieLoadError::InvalidCharTable {
+                offset: format.binary_width + e.valid_up_to(),
+            })?
+            .trim_end_matches(TERMINAL);
+
+        Ok((descriptor, header_width))
+    }
+
+    /// Encode the header in the Base64 format.
+    ///
+    /// The fields are padded to a whole Base64 char so the char table can
+    /// follow as plain text. The header width counts bytes, which matches
+    /// the JS reader's char count for ASCII tables.
+    ///
+    /// Panics if the header's version is not supported.
+    pub fn to_base64(&self) -> String {
+        let format = HeaderFormat::lookup(self.version).unwrap();
+        let mut fields = BitWriter::new();
+        let header_width = format.base64_width.div_ceil(6) + self.char_table.len();
+        fields.write(header_width as u32, HEADER_WIDTH_FIELD);
+        fields.write(self.version, VERSION_FIELD);
+        (format.write_base64)(self, &mut fields);
+
+        let mut encoded = fields.to_base64();
+        encoded.push_str(self.char_table);
+        encoded
+    }
+
+    /// Encode the header in the raw binary format.
+    ///
+    /// The char table is padded with terminals to a multiple of 8 bytes, so
+    /// the body that follows starts on a word boundary.
+    ///
+    /// Panics if the header's version is not supported.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let format = HeaderFormat::lookup(self.version).unwrap();
+        let header_width = (format.binary_width + self.char_table.len()).next_multiple_of(8);
+        assert!(header_width <= u16::MAX as usize, "Char table is too large to encode.");
+        let mut encoded = Vec::with_capacity(header_width);
+        encoded.extend_from_slice(&BINARY_MAGIC);
+        encoded.extend_from_slice(&(header_width as u16).to_be_bytes());
+        encoded.extend_from_slice(&(self.version as u16).to_be_bytes());
+        (format.write_binary)(self, &mut encoded);
+        encoded.extend_from_slice(self.char_table.as_bytes());
+        encoded.resize(header_width, TERMINAL as u8);
+        encoded
+    }
+
+    /// Re-target the descriptor at another header version. Fields the
+    /// version does not have are reset to their neutral value.
+    pub fn with_version(self, version: u32) -> Result<Header<'a>, TrieLoadError> {
+        let format = HeaderFormat::lookup(version)?;
+        Ok(Header {
+            version,
+            score_width: if format.scores { self.score_width } else { 0 },
+            ..self
+        })
+    }
+
+    /// Width in bits of a node in the body.
+    #[inline]
+    pub fn word_width(&self) -> usize {
+        (self.score_width + self.char_width + self.ptr_width + 1) as usize
+    }
+
+    /// Descriptor for the given version with every field neutral.
+    fn empty(version: u32) -> Header<'a> {
+        Header {
+            version,
+            offset: 0,
+            char_width: 0,
+            ptr_width: 0,
+            score_width: 0,
+            char_table: "",
+        }
+    }
+
+}
+
+
+
+// Header versions ----------------------------------------------------------
+
+/// Decoder and encoder for the fields of one header version.
+///
+/// Every version starts with the header width and version fields, which are
+/// handled generically, and ends with the char table. The functions here
+/// deal with the fixed fields in between.
+struct HeaderFormat {
+    version: u32,
+    /// Width in bits of the fixed fields in the Base64 encoding, including
+    /// the header width and version.
+    base64_width: usize,
+    /// Width in bytes of the fixed fields in the binary encoding, including
+    /// the magic number, header width and version.
+    binary_width: usize,
+    /// Whether the header has a score width, i.e. whether nodes in the body
+    /// can hold scores.
+    scores: bool,
+    /// Read the fields following the version from a Base64 header.
+    read_base64: fn(&str, &mut Header) -> Result<(), TrieLoadError>,
+    /// Read the fields following the version from a binary header.
+    read_binary: fn(&[u8], &mut Header),
+    /// Write the fields following the version in the Base64 encoding.
+    write_base64: fn(&Header, &mut BitWriter),
+    /// Write the fields following the version in the binary encoding.
+    write_binary: fn(&Header, &mut Vec<u8>),
+}
+
+
+/// Every header version this build can read and write.
+static FORMATS: [HeaderFormat; 2] = [
+    HeaderFormat {
+        version: VERSION,
+        base64_width: BASE64_FIELDS_WIDTH,
+        binary_width: BINARY_FIELDS_WIDTH,
+        scores: false,
+        read_base64: read_v0_base64,
+        read_binary: read_v0_binary,
+        write_base64: write_v0_base64,
+        write_binary: write_v0_binary,
+    },
+    HeaderFormat {
+        version: SCORED_VERSION,
+        base64_width: BASE64_FIELDS_WIDTH + SCORE_WIDTH_FIELD,
+        binary_width: BINARY_FIELDS_WIDTH + 1,
+        scores: true,
+        read_base64: read_v1_base64,
+        read_binary: read_v1_binary,
+        write_base64: write_v1_base64,
+        write_binary: write_v1_binary,
+    },
+];
+
+
+impl HeaderFormat {
+
+    /// Find the format for the given version.
+    fn lookup(version: u32) -> Result<&'static HeaderFormat, TrieLoadError> {
+        FORMATS.iter()
+            .find(|format| format.version == version)
+            .ok_or(TrieLoadError::UnsupportedVersion { version })
+    }
+
+}
+
+
+/// Offset of the first field after the version in a Base64 header, in bits.
+const BASE64_VERSION_END: usize = HEADER_WIDTH_FIELD + VERSION_FIELD;
+
+
+/// Offset of the first field after the version in a binary header, in bytes.
+const BINARY_VERSION_END: usize = 8;
+
+
+/// Version 0: pointer offset, char width and pointer width.
+fn read_v0_base64(header: &str, descriptor: &mut Header) -> Result<(), TrieLoadError> {
+    let mut ptr = BASE64_VERSION_END;
+
+    // Read pointer offset
+    let offset_sign = get_base64_field(header, ptr, OFFSET_SIGN_FIELD)?;
+    ptr += OFFSET_SIGN_FIELD;
+    let offset_val = get_base64_field(header, ptr, OFFSET_VAL_FIELD)?;
+    ptr += OFFSET_VAL_FIELD;
+    // Note: parens around -1 are significant; minus sign would otherwise be applied to result,
+    // i.e. -(1^0) != (-1)^0
+    descriptor.offset = (-1i32).pow(offset_sign) * (offset_val as i32);
+
+    // Get segment widths
+    descriptor.char_width = get_base64_field(header, ptr, CHAR_WIDTH_FIELD)?;
+    ptr += CHAR_WIDTH_FIELD;
+    descriptor.ptr_width = get_base64_field(header, ptr, POINTER_WIDTH_FIELD)?;
+
+    Ok(())
+}
+
+
+fn read_v0_binary(bytes: &[u8], descriptor: &mut Header) {
+    let fields = &bytes[BINARY_VERSION_END..];
+    descriptor.offset = i32::from_be_bytes([fields[0], fields[1], fields[2], fields[3]]);
+    descriptor.char_width = fields[4] as u32;
+    descriptor.ptr_width = fields[5] as u32;
+}
+
+
+fn write_v0_base64(descriptor: &Header, w: &mut BitWriter) {
+    w.write((descriptor.offset < 0) as u32, OFFSET_SIGN_FIELD);
+    w.write(descriptor.offset.unsigned_abs(), OFFSET_VAL_FIELD);
+    w.write(descriptor.char_width, CHAR_WIDTH_FIELD);
+    w.write(descriptor.ptr_width, POINTER_WIDTH_FIELD);
+}
+
+
+fn write_v0_binary(descriptor: &Header, out: &mut Vec<u8>) {
+    out.extend_from_slice(&descriptor.offset.to_be_bytes());
+    out.push(descriptor.char_width as u8);
+    out.push(descriptor.ptr_width as u8);
+}
+
+
+/// Version 1: version 0 followed by the score width.
+fn read_v1_base64(header: &str, descriptor: &mut Header) -> Result<(), TrieLoadError> {
+    read_v0_base64(header, descriptor)?;
+    descriptor.score_width = get_base64_field(header, BASE64_FIELDS_WIDTH, SCORE_WIDTH_FIELD)?;
+    Ok(())
+}
+
+
+fn read_v1_binary(bytes: &[u8], descriptor: &mut Header) {
+    read_v0_binary(bytes, descriptor);
+    descriptor.score_width = bytes[BINARY_FIELDS_WIDTH] as u32;
+}
+
+
+fn write_v1_base64(descriptor: &Header, w: &mut BitWriter) {
+    write_v0_base64(descriptor, w);
+    w.write(descriptor.score_width, SCORE_WIDTH_FIELD);
+}
+
+
+fn write_v1_binary(descriptor: &Header, out: &mut Vec<u8>) {
+    write_v0_binary(descriptor, out);
+    out.push(descriptor.score_width as u8);
+}
+
+
+
+// Private support methods --------------------------------------------------
+
+/// Extract a window of bits from a base-64 encoded sequence.
+///
+/// This method reads binary from the encoded string directly. It is ported
+/// from the Typescript implementation, which uses it for every lookup due
+/// to lack of better bit-level tooling in that language.
+///
+/// Use this for non performance-sensitive operations; i.e., never use this
+/// for lookups in the trie, which are read from the decoded body instead.
+///
+/// Panics if the window extends past the end of the string.
+#[inline]
+fn get_base64_field(base64: &str, start: usize, bit_length: usize) -> Result<u32, TrieLoadError> {
+    let bytes: &[u8] = base64.as_bytes();
+    let start_char: usize = start / 6;
+    let start_bit_offset: usize = start % 6;
+    let end_bit: usize = start_bit_offset + bit_length;
+    let char_len: usize = end_bit.div_ceil(6);
+    let mask: u32 = (0x1 << bit_length as u32) - 1;
+
+    let mut chunk: u32 = 0;
+    for i in 0..char_len {
+        let idx: usize = start_char + i;
+        chunk <<= 6;
+        chunk |= decode_symbol(bytes[idx] as char, idx)?;
+    }
+
+    let right_pad = end_bit % 6;
+    if right_pad > 0 {
+        chunk >>= 6 - right_pad;
+    }
+
+    Ok(chunk & mask)
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let (header, body_start) = Header::from_base64("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+        assert_eq!(header, Header {
+            version: 0,
+            offset: 1,
+            char_width: 3,
+            ptr_width: 2,
+            score_width: 0,
+            char_table: "fboarz",
+        });
+        assert_eq!(body_start, 16);
+        assert_eq!(header.word_width(), 6);
+        assert_eq!(header.to_base64(), "BAAAAABAwIfboarz");
+
+        let binary = header.to_binary();
+        assert_eq!(binary.len(), 24);
+        assert_eq!(Header::from_binary(&binary).unwrap(), (header, binary.len()));
+    }
+
+    #[test]
+    fn test_scored_header_round_trip() {
+        let header = Header {
+            version: SCORED_VERSION,
+            offset: -3,
+            char_width: 5,
+            ptr_width: 9,
+            score_width: 7,
+            char_table: "ABC",
+        };
+        assert_eq!(header.word_width(), 22);
+
+        let base64 = header.to_base64();
+        assert_eq!(Header::from_base64(&base64).unwrap(), (header, base64.len()));
+
+        let (header, _) = Header::from_base64(&base64).unwrap();
+        let binary = header.to_binary();
+        assert_eq!(binary.len(), 24);
+        assert_eq!(Header::from_binary(&binary).unwrap(), (header, binary.len()));
+    }
+
+    #[test]
+    fn test_header_formats() {
+        for (i, format) in FORMATS.iter().enumerate() {
+            assert_eq!(HeaderFormat::lookup(format.version).unwrap().version, format.version);
+            // Each version only adds fields to the one before.
+            if i > 0 {
+                assert!(format.base64_width > FORMATS[i - 1].base64_width);
+                assert!(format.binary_width > FORMATS[i - 1].binary_width);
+            }
+        }
+        assert_eq!(HeaderFormat::lookup(2).err(),
+                   Some(TrieLoadError::UnsupportedVersion { version: 2 }));
+    }
+
+    // Re-targeting drops the fields the new version doesn't have.
+    #[test]
+    fn test_with_version() {
+        let header = Header {
+            version: SCORED_VERSION,
+            offset: 1,
+            char_width: 3,
+            ptr_width: 2,
+            score_width: 4,
+            char_table: "ab",
+        };
+        let v0 = header.clone().with_version(VERSION).unwrap();
+        assert_eq!(v0, Header { version: VERSION, score_width: 0, ..header.clone() });
+        assert_eq!(v0.with_version(SCORED_VERSION).unwrap(),
+                   Header { score_width: 0, ..header.clone() });
+        assert_eq!(header.with_version(2).err(),
+                   Some(TrieLoadError::UnsupportedVersion { version: 2 }));
+    }
+
+    // A version 1 header too short for its own fields is caught before any
+    // of them are read.
+    #[test]
+    fn test_from_base64_short_for_version() {
+        let header = Header {
+            version: SCORED_VERSION,
+            offset: 1,
+            char_width: 3,
+            ptr_width: 2,
+            score_width: 4,
+            char_table: "",
+        };
+        let mut base64 = header.to_base64();
+        // Rewrite the header width to that of a version 0 header.
+        base64.replace_range(..2, "Ao");
+        assert_eq!(Header::from_base64(&base64).err(),
+                   Some(TrieLoadError::HeaderWidthExceedsInput { header_width: 10, len: 11 }));
+    }
+
+    #[test]
+    fn test_from_binary_errors() {
+        let (header, _) = Header::from_base64("BAAAAABAwIfboarz").unwrap();
+        let binary = header.to_binary();
+
+        assert_eq!(Header::from_binary(&binary[..10]).err(),
+                   Some(TrieLoadError::TruncatedHeader { len: 10, expected: 14 }));
+        assert_eq!(Header::from_binary(&binary[..16]).err(),
+                   Some(TrieLoadError::HeaderWidthExceedsInput { header_width: 24, len: 16 }));
+
+        let mut bad = binary.clone();
+        bad[0] = b'X';
+        assert_eq!(Header::from_binary(&bad).err(), Some(TrieLoadError::InvalidMagic));
+
+        let mut bad = binary.clone();
+        bad[7] = 9;
+        assert_eq!(Header::from_binary(&bad).err(),
+                   Some(TrieLoadError::UnsupportedVersion { version: 9 }));
+
+        let mut bad = binary.clone();
+        bad[16] = 0xff;
+        assert_eq!(Header::from_binary(&bad).err(),
+                   Some(TrieLoadError::InvalidCharTable { offset: 16 }));
+    }
+
+
+    // get_base64_field (bit extraction) ------------------------------------
+
+    // Test basic accuracy of bit window extraction
+    #[test]
+    fn test_get_base64_field() {
+        let test_str = String::from("foo+");
+        // For reference, "foo+" encodes the binary:
+        // 0111 1110 1000 1010 0011 1110
+        assert_eq!(get_base64_field(&test_str, 0, 4).unwrap(), 7);
+        assert_eq!(get_base64_field(&test_str, 2, 4).unwrap(), 15);
+        assert_eq!(get_base64_field(&test_str, 8, 8).unwrap(), 138);
+        assert_eq!(get_base64_field(&test_str, 10, 13).unwrap(), 1311);
+    }
+
+    // Test panic when window size exceeds bounds
+    #[test]
+    #[should_panic]
+    fn test_get_base64_field_bounds() {
+        let test_str = String::from("foo+");
+        let _ = get_base64_field(&test_str, 17, 8);
+    }
+
+    // Test panic when starts out of bounds
+    #[test]
+    #[should_panic]
+    fn test_get_base64_field_oob_start() {
+        let test_str = String::from("foo+");
+        let _ = get_base64_field(&test_str, 25, 1);
+    }
+
+}
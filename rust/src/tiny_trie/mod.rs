@@ -1,3 +1,25 @@
 pub mod packed;
-mod constants;
-mod base64;
\ No newline at end of file
+pub mod builder;
+pub mod pattern;
+pub mod error;
+pub mod constants;
+pub mod binary;
+pub mod verify;
+pub mod query;
+pub mod normalize;
+pub mod glob;
+mod fuzzy;
+mod anagram;
+mod prefixes;
+mod set_ops;
+mod sample;
+mod rank;
+mod base64;
+mod bit_writer;
+mod header;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(test)]
+pub(crate) mod fixtures;
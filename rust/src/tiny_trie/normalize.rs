@@ -0,0 +1,327 @@
+//! Normalization of words and queries for non-English word lists.
+//!
+//! A trie matches chars exactly, so `É` and `E`, or `a` and `A`, are
+//! different letters to it. A `Normalizer` maps text to the form the word
+//! list is stored in. Give the same normalizer to the `TrieBuilder` that
+//! builds a list and to the `PackedTrie` (or `WordBank`) that searches it,
+//! so that words and queries are normalized alike.
+use std::collections::BTreeSet;
+use std::iter;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+
+/// Unicode normalization form of the normalized text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalForm {
+    /// Canonical composition; accented letters are single chars.
+    Nfc,
+    /// Canonical decomposition; accents are separate combining chars, and
+    /// so separate letters in the trie.
+    Nfd,
+}
+
+
+/// Case folding applied to every char.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// Leave case as is.
+    Preserve,
+    /// Fold to upper case, as the word lists in `data/dist` are.
+    Upper,
+    /// Fold to lower case.
+    Lower,
+}
+
+
+/// Configurable text normalization, applied in the following order:
+///
+///  1. Case folding.
+///  2. Accent stripping, except for letters that keep their accents.
+///  3. Replacement rules, e.g. digraphs that fill a single square.
+///  4. Composition or decomposition into the normal form.
+///
+/// Chars that aren't letters, like the '*' wildcard, pass through
+/// unchanged, so needles can be normalized as they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalizer {
+    form: NormalForm,
+    case: Case,
+    strip_accents: bool,
+    /// Letters exempt from accent stripping, in both cases.
+    keep_accents: BTreeSet<char>,
+    /// Replacements, longest sequence first.
+    rules: Vec<(String, String)>,
+}
+
+
+impl Normalizer {
+
+    /// Create a normalizer that only composes text into NFC.
+    pub fn new() -> Normalizer {
+        Normalizer {
+            form: NormalForm::Nfc,
+            case: Case::Preserve,
+            strip_accents: false,
+            keep_accents: BTreeSet::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Upper case without accents, matching the English word lists.
+    pub fn english() -> Normalizer {
+        Normalizer::new().case(Case::Upper).strip_accents(true)
+    }
+
+    /// Upper case without accents, with the Œ and Æ ligatures spelled out,
+    /// as French crosswords write them.
+    pub fn french() -> Normalizer {
+        Normalizer::english().rule("Œ", "OE").rule("Æ", "AE")
+    }
+
+    /// Upper case without accents, except that Ñ is a letter of its own.
+    ///
+    /// Puzzles that treat CH and LL as single letters can add digraph rules
+    /// on top, e.g. `.rule("LL", "Ŀ")`.
+    pub fn spanish() -> Normalizer {
+        Normalizer::english().keep_accent('Ñ')
+    }
+
+    /// Set the Unicode normalization form of the output.
+    pub fn form(mut self, form: NormalForm) -> Normalizer {
+        self.form = form;
+        self
+    }
+
+    /// Set the case folding.
+    pub fn case(mut self, case: Case) -> Normalizer {
+        self.case = case;
+        self
+    }
+
+    /// Set whether accents and other combining marks are removed.
+    pub fn strip_accents(mut self, strip: bool) -> Normalizer {
+        self.strip_accents = strip;
+        self
+    }
+
+    /// Exempt a letter, in either case, from accent stripping.
+    pub fn keep_accent(mut self, letter: char) -> Normalizer {
+        self.keep_accents.insert(letter);
+        self.keep_accents.extend(letter.to_uppercase());
+        self.keep_accents.extend(letter.to_lowercase());
+        self
+    }
+
+    /// Replace a sequence with another, e.g. a digraph with the single
+    /// symbol that stands for it in the trie.
+    ///
+    /// Sequences are matched after case folding and accent stripping, so
+    /// they should be given in that form. Where sequences overlap the
+    /// longest one wins.
+    pub fn rule(mut self, from: &str, to: &str) -> Normalizer {
+        assert!(!from.is_empty(), "Cannot replace an empty sequence.");
+        self.rules.push((from.to_string(), to.to_string()));
+        self.rules.sort_by_key(|rule| std::cmp::Reverse(rule.0.len()));
+        self
+    }
+
+    /// Normalize text.
+    pub fn normalize(&self, text: &str) -> String {
+        // ASCII is already composed and has no accents to strip.
+        let stripped = if text.is_ascii() {
+            match self.case {
+                Case::Preserve => text.to_string(),
+                Case::Upper => text.to_ascii_uppercase(),
+                Case::Lower => text.to_ascii_lowercase(),
+            }
+        } else {
+            let mut folded = String::with_capacity(text.len());
+            for c in text.nfc() {
+                match self.case {
+                    Case::Preserve => folded.push(c),
+                    Case::Upper => folded.extend(c.to_uppercase()),
+                    Case::Lower => folded.extend(c.to_lowercase()),
+                }
+            }
+            if self.strip_accents {
+                let mut stripped = String::with_capacity(folded.len());
+                for c in folded.chars() {
+                    if self.keep_accents.contains(&c) {
+                        stripped.push(c);
+                    } else {
+                        stripped.extend(iter::once(c).nfd().filter(|&m| !is_combining_mark(m)));
+                    }
+                }
+                stripped
+            } else {
+                folded
+            }
+        };
+
+        let replaced = self.replace(stripped);
+        match self.form {
+            NormalForm::Nfc if replaced.is_ascii() => replaced,
+            NormalForm::Nfc => replaced.nfc().collect(),
+            NormalForm::Nfd => replaced.nfd().collect(),
+        }
+    }
+
+    /// Normalize the runs of text between the given special chars, like a
+    /// pattern's wildcards, leaving the special chars themselves alone.
+    ///
+    /// Returns each normalized run with the special char that ends it, or
+    /// `None` for the last run.
+    pub fn normalize_runs(&self, text: &str, specials: &[char]) -> Vec<(String, Option<char>)> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for (offset, c) in text.char_indices() {
+            if specials.contains(&c) {
+                runs.push((self.normalize(&text[start..offset]), Some(c)));
+                start = offset + c.len_utf8();
+            }
+        }
+        runs.push((self.normalize(&text[start..]), None));
+        runs
+    }
+
+    /// Apply the replacement rules.
+    fn replace(&self, text: String) -> String {
+        if self.rules.is_empty() {
+            return text;
+        }
+        let mut replaced = String::with_capacity(text.len());
+        let mut rest = &text[..];
+        'scan: while let Some(c) = rest.chars().next() {
+            for (from, to) in &self.rules {
+                if rest.starts_with(from.as_str()) {
+                    replaced.push_str(to);
+                    rest = &rest[from.len()..];
+                    continue 'scan;
+                }
+            }
+            replaced.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        replaced
+    }
+
+}
+
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer::new()
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::query::SearchOptions;
+
+    #[test]
+    fn test_normalize_form() {
+        let decomposed = "E\u{301}TE\u{301}";
+        assert_eq!(Normalizer::new().normalize(decomposed), "ÉTÉ");
+        assert_eq!(Normalizer::new().form(NormalForm::Nfd).normalize("ÉTÉ"), decomposed);
+        assert_eq!(Normalizer::new().normalize("été"), "été");
+    }
+
+    #[test]
+    fn test_normalize_case_and_accents() {
+        let english = Normalizer::english();
+        assert_eq!(english.normalize("Café"), "CAFE");
+        assert_eq!(english.normalize("cafe\u{301}"), "CAFE");
+        assert_eq!(english.normalize("naïve*"), "NAIVE*");
+        assert_eq!(english.normalize("straße"), "STRASSE");
+        assert_eq!(english.normalize("C*A*E"), "C*A*E");
+
+        let lower = Normalizer::new().case(Case::Lower).strip_accents(true);
+        assert_eq!(lower.normalize("ÉCOLE"), "ecole");
+        assert_eq!(Normalizer::new().case(Case::Lower).normalize("ÉCOLE"), "école");
+    }
+
+    #[test]
+    fn test_normalize_languages() {
+        let french = Normalizer::french();
+        assert_eq!(french.normalize("cœur"), "COEUR");
+        assert_eq!(french.normalize("Ex æquo"), "EX AEQUO");
+        assert_eq!(french.normalize("Hôtel"), "HOTEL");
+
+        let spanish = Normalizer::spanish();
+        assert_eq!(spanish.normalize("año"), "AÑO");
+        assert_eq!(spanish.normalize("an\u{303}o"), "AÑO");
+        assert_eq!(spanish.normalize("canción"), "CANCION");
+        assert_eq!(spanish.form(NormalForm::Nfd).normalize("año"), "AN\u{303}O");
+    }
+
+    #[test]
+    fn test_normalize_digraphs() {
+        let traditional = Normalizer::spanish().rule("LL", "Ŀ").rule("CH", "Ç");
+        assert_eq!(traditional.normalize("llamada"), "ĿAMADA");
+        assert_eq!(traditional.normalize("Chichón"), "ÇIÇON");
+        assert_eq!(traditional.normalize("calle").chars().count(), 4);
+
+        // The longest sequence wins.
+        let rules = Normalizer::new().rule("A", "1").rule("AB", "2");
+        assert_eq!(rules.normalize("ABA"), "21");
+    }
+
+    // Words and queries are normalized alike.
+    #[test]
+    fn test_normalized_trie() {
+        let mut builder = TrieBuilder::new();
+        builder.set_normalizer(Normalizer::french());
+        builder.extend(vec!["Café", "cœur", "élève", "CAFE"]);
+        let pt = builder.build();
+
+        assert_eq!(pt.word_count(), 3);
+        assert_eq!(pt.search_pfx_iter("").collect::<Vec<String>>(), vec!["CAFE", "COEUR", "ELEVE"]);
+        assert!(pt.test("café"));
+        assert!(pt.test("e\u{301}le\u{300}ve"));
+        assert_eq!(pt.search_iter("c*f*").collect::<Vec<String>>(), vec!["CAFE"]);
+        assert_eq!(pt.count("é****"), 1);
+        assert_eq!(pt.rank("Cœur"), Some(1));
+        assert_eq!(pt.compile("cœ**").unwrap().count(), 1);
+        assert_eq!(pt.anagrams("fécà", 0), vec!["CAFE"]);
+
+        // Without the normalizer only the stored form matches.
+        let mut raw = pt;
+        raw.set_normalizer(None);
+        assert!(!raw.test("café"));
+        assert!(raw.test("CAFE"));
+    }
+
+    #[test]
+    fn test_normalize_runs() {
+        let english = Normalizer::english();
+        let runs = english.normalize_runs("cxté%", &['x', '%']);
+        assert_eq!(runs, vec![(String::from("C"), Some('x')),
+                              (String::from("TE"), Some('%')),
+                              (String::new(), None)]);
+        assert_eq!(english.normalize_runs("", &['x']), vec![(String::new(), None)]);
+    }
+
+    // A custom wildcard is recognised before the needle is normalized, so a
+    // letter wildcard isn't folded into a literal.
+    #[test]
+    fn test_normalized_trie_letter_wildcard() {
+        let mut builder = TrieBuilder::new();
+        builder.set_normalizer(Normalizer::english());
+        builder.extend(vec!["cat", "cot", "cut", "xix"]);
+        let pt = builder.build();
+
+        let options = SearchOptions { wildcard: 'x', ..SearchOptions::new() };
+        assert_eq!(pt.search_with("cxt", &options), vec!["CAT", "COT", "CUT"]);
+        assert_eq!(pt.search_with("xIx", &options), vec!["XIX"]);
+        assert!(pt.search_with("cXt", &options).is_empty());
+        assert_eq!(pt.search_with("cót", &options), vec!["COT"]);
+    }
+
+}
@@ -1,22 +1,78 @@
-use std::collections::{HashMap, LinkedList};
-use bit_vec;
-use ::tiny_trie::constants::{CHAR_WIDTH_FIELD,
-                             HEADER_WIDTH_FIELD,
-                             LAST_MASK,
-                             OFFSET_SIGN_FIELD,
-                             OFFSET_VAL_FIELD,
-                             POINTER_WIDTH_FIELD,
+use std::borrow::Cow;
+use std::collections::{HashMap, LinkedList, VecDeque};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use ::tiny_trie::constants::{LAST_MASK,
                              PTR_SHIFT,
-                             TERMINAL,
-                             VERSION,
-                             VERSION_FIELD};
-use ::tiny_trie::base64::{char_to_int};
+                             TERMINAL};
+use ::tiny_trie::base64;
+use ::tiny_trie::error::TrieLoadError;
+use ::tiny_trie::header::Header;
+use ::tiny_trie::normalize::Normalizer;
+use ::tiny_trie::pattern::{vars_allow, CharSet, Pattern, VarRef};
+use ::tiny_trie::query::{SearchOptions, SearchOrder};
 
 
 // Constants ----------------------------------------------------------------
 
 /// The standard wildcard character.
-const DEFAULT_WILDCARD: &'static char = &'*';
+pub const DEFAULT_WILDCARD: char = '*';
+
+
+/// A single decoded node from the trie body.
+pub(crate) struct Node {
+    /// Index of the node's char in the char table.
+    pub char_idx: u32,
+    /// Index of the first node in the next level.
+    pub next: usize,
+    /// Whether this is the last node in its level.
+    pub last: bool,
+    /// Score of the word ending at a terminal node, or the best score of any
+    /// word through any other node. Always 0 for tries without scores.
+    pub score: u32,
+}
+
+
+/// Summary of the words below a level, used to answer counts of wildcard
+/// suffixes without visiting the subtree.
+#[derive(Clone, Copy)]
+pub(crate) struct LevelStats {
+    /// Number of words below the level, saturating at `u64::MAX`.
+    pub count: u64,
+    /// Length of the shortest word below the level.
+    pub min_len: u16,
+    /// Length of the longest word below the level, saturating at
+    /// `u16::MAX`. Tries that pass `verify` never saturate.
+    pub max_len: u16,
+}
+
+
+impl LevelStats {
+
+    /// Add the words below a child level, one char longer than in the child.
+    fn add_child(&mut self, child: LevelStats) {
+        if child.count > 0 {
+            self.count = self.count.saturating_add(child.count);
+            self.min_len = self.min_len.min(child.min_len.saturating_add(1));
+            self.max_len = self.max_len.max(child.max_len.saturating_add(1));
+        }
+    }
+
+}
+
+
+/// Position within a level on the level statistics stack.
+struct StatsFrame {
+    /// Index of the level's first node.
+    level: usize,
+    /// Index of the next node to examine in the level.
+    cursor: usize,
+    /// Whether the last node in the level has been examined.
+    done: bool,
+    /// Statistics of the nodes examined so far.
+    acc: LevelStats,
+}
 
 
 /// BFS trie search node
@@ -33,95 +89,281 @@ struct SearchNode {
 /// Packed binary trie that implements test and search with wildcard and
 /// prefix matching methods.
 ///
-/// The trie is instantiated from its Base64 binary-encoded string. The
-/// format is read natively; the trie is never processed in memory. This
-/// keeps instantiation time effectively instantaneous, memory requirements
+/// The trie is instantiated from its Base64 binary-encoded string, or from
+/// the equivalent raw binary encoding (see the `binary` module). The format
+/// is read natively; the trie is never processed in memory. This keeps
+/// instantiation time effectively instantaneous, memory requirements
 /// minimal, and loses almost nothing in search performance.
 ///
+/// A trie loaded from raw binary borrows its buffer for the lifetime `'a`.
+/// Tries loaded from Base64 own their data and can be `PackedTrie<'static>`.
+///
 /// The trade-off from the full Trie implementation is that the trie is
-/// frozen; elements cannot be added or removed.
-pub struct PackedTrie {
+/// frozen; elements cannot be added or removed. In exchange it is `Send` and
+/// `Sync`, so one trie can serve searches from many threads at once.
+pub struct PackedTrie<'a> {
+    version: u32,
     offset: i32,
-    data: bit_vec::BitVec,
+    // Trie body, one bit-packed node after another, as big-endian words.
+    data: Cow<'a, [u64]>,
     // TODO(jnu) optimized hashmap for short char keys
     table: HashMap<char, u32>,
     // TODO(jnu) could use array here? but knowing size at compile time impossible.
     inverse_table: HashMap<u32, char>,
+    // Position of each char index when the chars are sorted, with the
+    // terminal first. Used to walk levels in lexicographic order.
+    char_order: Vec<u32>,
     word_width: usize,
-    pointer_mask: u32,
-    char_mask: u32,
+    pointer_mask: u64,
+    char_mask: u64,
     char_shift: u32,
+    score_mask: u64,
+    score_shift: u32,
+    // Subtree statistics, computed on first use by count queries.
+    stats: OnceLock<Vec<LevelStats>>,
+    // Applied to needles and words passed to the search methods.
+    normalizer: Option<Normalizer>,
 }
 
 
-impl PackedTrie {
+impl<'a> PackedTrie<'a> {
 
     /// Load a packed trie from its Base64 binary encoding.
+    ///
+    /// Panics if the input is malformed. Use `try_from_str` to load input
+    /// that may be corrupt, such as user-supplied or downloaded files.
     #[inline]
-    pub fn from(packed: &str) -> PackedTrie {
-        // Read the header width from the initial field.
-        let header_char_width = get_base64_field(packed,
-                                                 0,
-                                                 HEADER_WIDTH_FIELD) as usize;
-
-        // Cut a slice for the header alone and work with this for the rest
-        // of the init processing.
-        let header = packed.get(..header_char_width).unwrap();
-        // Start parsing header after the width field.
-        let mut ptr: usize = HEADER_WIDTH_FIELD;
-
-        // Read the version and verify it against what we know how to parse.
-        // TODO(jnu) support for multiple versions if/when necessary.
-        let version = get_base64_field(header, ptr, VERSION_FIELD);
-        ptr += VERSION_FIELD;
-
-        if version != VERSION {
-            panic!("Invalid header version {}. Expected {}.", version, VERSION);
-        }
-
-        // Read pointer offset
-        let offset_sign = get_base64_field(header, ptr, OFFSET_SIGN_FIELD);
-        ptr += OFFSET_SIGN_FIELD;
-        let offset_val = get_base64_field(header, ptr, OFFSET_VAL_FIELD);
-        ptr += OFFSET_VAL_FIELD;
-        // Note: parens around -1 are significant; minus sign would otherwise be applied to result,
-        // i.e. -(1^0) != (-1)^0
-        let offset = (-1i32).pow(offset_sign) * (offset_val as i32);
-
-        // Get segment widths
-        let char_width = get_base64_field(header, ptr, CHAR_WIDTH_FIELD);
-        ptr += CHAR_WIDTH_FIELD;
-        let ptr_width = get_base64_field(header, ptr, POINTER_WIDTH_FIELD);
-        ptr += POINTER_WIDTH_FIELD;
-
-        // Derive other useful widths and masks from segment widths
-        let word_width = (char_width + ptr_width + 1) as usize;
-        let pointer_mask = (0x1 << ptr_width) - 1;
-        let char_mask = (0x1 << char_width) - 1;
-        let char_shift = 1 + ptr_width;
-
-        // The rest of the header is the character table. Parse this.
-        let char_tbl_start_char = (ptr as f32 / 6.0).ceil() as usize;
-        let char_table = header.get(char_tbl_start_char..).unwrap();
-
-        let table = build_char_table(&char_table);
-        let inverse_table = build_inverse_char_table(&char_table);
-
-        // Now process trie body. Restructure as a bitvec.
-        // TODO(jnu) benchmark bitvec against native reads
-        let body = packed.get(header_char_width..).unwrap();
-        let data = bit_vec_from_base64(&body);
+    pub fn from(packed: &str) -> PackedTrie<'a> {
+        match PackedTrie::try_from_str(packed) {
+            Ok(trie) => trie,
+            Err(e) => panic!("{}", e),
+        }
+    }
 
-        PackedTrie {
-            offset,
+    /// Load a packed trie from its Base64 binary encoding, reporting any
+    /// problem with the encoding as an error.
+    ///
+    /// Only the header is checked. Call `verify` before searching a body
+    /// that may be malformed.
+    pub fn try_from_str(packed: &str) -> Result<PackedTrie<'a>, TrieLoadError> {
+        let (header, body_start) = Header::from_base64(packed)?;
+        // The body is decoded once, so lookups never have to touch the Base64.
+        let body = base64::decode(&packed[body_start..], body_start)?;
+        PackedTrie::from_header(&header, Cow::Owned(words_from_bytes(&body).into_owned()))
+    }
+
+    /// Load a packed trie from its raw binary encoding, borrowing the buffer.
+    ///
+    /// Nothing is decoded or copied apart from the small char table, so this
+    /// is effectively instantaneous even for large word lists. The buffer can
+    /// be anything that derefs to bytes, such as a memory-mapped file. Use
+    /// `binary::base64_to_binary` to produce the encoding.
+    ///
+    /// The body is read in place as 64-bit words, which needs the buffer to
+    /// start on an 8-byte boundary. Page-aligned buffers like mmaps always
+    /// do; the body of any other buffer is copied.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<PackedTrie<'a>, TrieLoadError> {
+        let (header, body_start) = Header::from_binary(bytes)?;
+        PackedTrie::from_header(&header, words_from_bytes(&bytes[body_start..]))
+    }
+
+    /// Assemble a packed trie from its parsed header and body bytes.
+    fn from_header(header: &Header, data: Cow<'a, [u64]>) -> Result<PackedTrie<'a>, TrieLoadError> {
+        // Derive other useful widths and masks from segment widths. Nodes
+        // are read into a u64, so anything wider can't be represented.
+        let word_width = header.word_width();
+        if word_width > 64 || header.ptr_width > 32 || header.score_width > 32 {
+            return Err(TrieLoadError::WordTooWide { word_width });
+        }
+        let pointer_mask = (0x1 << header.ptr_width) - 1;
+        let char_mask = (0x1 << header.char_width) - 1;
+        let char_shift = 1 + header.ptr_width;
+        let score_mask = (0x1 << header.score_width) - 1;
+        let score_shift = char_shift + header.char_width;
+
+        // Every char, plus the implicit terminal, needs a distinct index.
+        let entries = header.char_table.chars().count() + 1;
+        if entries as u64 > 0x1u64 << header.char_width {
+            return Err(TrieLoadError::CharTableOverflow {
+                entries,
+                char_width: header.char_width,
+            });
+        }
+
+        Ok(PackedTrie {
+            version: header.version,
+            offset: header.offset,
             data,
-            table,
-            inverse_table,
+            table: build_char_table(header.char_table),
+            inverse_table: build_inverse_char_table(header.char_table),
+            char_order: build_char_order(header.char_table),
             word_width,
             pointer_mask,
             char_mask,
             char_shift,
+            score_mask,
+            score_shift,
+            stats: OnceLock::new(),
+            normalizer: None,
+        })
+    }
+
+    /// Encode the trie in the Base64 format, as read by `from`.
+    ///
+    /// Re-encoding a loaded trie reproduces the encoding it was loaded from,
+    /// less any padding after the last node.
+    pub fn to_base64(&self) -> String {
+        let char_table = self.char_table();
+        let mut encoded = self.header(&char_table).to_base64();
+        let body: Vec<u8> = self.data.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        encoded.push_str(&base64::encode(&body, self.body_len() * self.word_width));
+        encoded
+    }
+
+    /// Encode the trie in the raw binary format, as read by `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let char_table = self.char_table();
+        let mut encoded = self.header(&char_table).to_binary();
+        // Words are stored big-endian, so their in-memory bytes are already
+        // the encoded body.
+        let words = (self.body_len() * self.word_width).div_ceil(64);
+        for word in &self.data[..words] {
+            encoded.extend_from_slice(&word.to_ne_bytes());
+        }
+        encoded
+    }
+
+    /// Copy the trie into the given header version, e.g. to upgrade a
+    /// version 0 word list to a newer format.
+    ///
+    /// Nodes are rewritten if the new version lays them out differently.
+    /// Scores are dropped if the new version has none; a trie without
+    /// scores gains a zero-width score field, so every word still scores 0.
+    pub fn to_version(&self, version: u32) -> Result<PackedTrie<'static>, TrieLoadError> {
+        let char_table = self.char_table();
+        let header = self.header(&char_table).with_version(version)?;
+        if header.word_width() == self.word_width {
+            let mut trie = PackedTrie::from_header(&header, Cow::Owned(self.data.to_vec()))?;
+            trie.normalizer = self.normalizer.clone();
+            return Ok(trie);
+        }
+
+        // Only the score field can change width. It leads the node, so the
+        // rest of the node can be copied as is.
+        let word_width = header.word_width();
+        let rest_mask = u64::MAX >> (64 - self.score_shift);
+        let score_mask = (0x1u64 << header.score_width) - 1;
+        let nodes = self.body_len();
+        let mut data = vec![0u64; (nodes * word_width).div_ceil(64)];
+        for ptr in 0..nodes {
+            let word = self.read_word(ptr);
+            let score = (word >> self.score_shift) & score_mask;
+            set_words_field(&mut data,
+                            ptr * word_width,
+                            word_width,
+                            (score << self.score_shift) | (word & rest_mask));
+        }
+        let mut trie = PackedTrie::from_header(&header, Cow::Owned(data))?;
+        trie.normalizer = self.normalizer.clone();
+        Ok(trie)
+    }
+
+    /// Detach the trie from the buffer it was loaded from, copying the body
+    /// if it was borrowed.
+    pub fn into_owned(self) -> PackedTrie<'static> {
+        PackedTrie {
+            version: self.version,
+            offset: self.offset,
+            data: Cow::Owned(self.data.into_owned()),
+            table: self.table,
+            inverse_table: self.inverse_table,
+            char_order: self.char_order,
+            word_width: self.word_width,
+            pointer_mask: self.pointer_mask,
+            char_mask: self.char_mask,
+            char_shift: self.char_shift,
+            score_mask: self.score_mask,
+            score_shift: self.score_shift,
+            stats: self.stats,
+            normalizer: self.normalizer,
+        }
+    }
+
+    /// Normalize needles and words passed to the search methods, e.g. to
+    /// fold case and accents the way the word list was built.
+    ///
+    /// Methods taking a `Pattern` use it as given.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> PackedTrie<'a> {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Set or clear the normalizer (see `with_normalizer`).
+    pub fn set_normalizer(&mut self, normalizer: Option<Normalizer>) {
+        self.normalizer = normalizer;
+    }
+
+    /// The normalizer applied to needles, if any.
+    #[inline]
+    pub fn normalizer(&self) -> Option<&Normalizer> {
+        self.normalizer.as_ref()
+    }
+
+    /// Normalize a needle or word passed to a search method.
+    #[inline]
+    pub(crate) fn normalize<'b>(&self, text: &'b str) -> Cow<'b, str> {
+        match self.normalizer {
+            Some(ref normalizer) => Cow::Owned(normalizer.normalize(text)),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Normalize a needle and parse it into a pattern.
+    ///
+    /// Only the runs between wildcards are normalized, so a wildcard the
+    /// normalizer would change, like a letter under case folding, still
+    /// matches any char.
+    pub(crate) fn needle_pattern(&self, needle: &str, wildcard: char) -> Pattern {
+        let normalizer = match self.normalizer {
+            Some(ref normalizer) => normalizer,
+            None => return Pattern::from_wildcard(needle, wildcard),
+        };
+        let mut sets = Vec::new();
+        for (run, end) in normalizer.normalize_runs(needle, &[wildcard]) {
+            sets.extend(run.chars().map(CharSet::literal));
+            if end.is_some() {
+                sets.push(CharSet::Any);
+            }
         }
+        Pattern::new(sets)
+    }
+
+    /// Describe the trie's header, borrowing the char table from the given
+    /// string (see `char_table`).
+    fn header<'b>(&self, char_table: &'b str) -> Header<'b> {
+        Header {
+            version: self.version,
+            offset: self.offset,
+            char_width: self.char_mask.count_ones(),
+            ptr_width: self.pointer_mask.count_ones(),
+            score_width: self.score_mask.count_ones(),
+            char_table,
+        }
+    }
+
+    /// The chars in the char table, in index order.
+    fn char_table(&self) -> String {
+        (1..=self.char_count() as u32).map(|char_idx| self.char_at(char_idx)).collect()
+    }
+
+    /// Number of nodes in the body, not counting zeroed padding at the end.
+    ///
+    /// The final node of a level is flagged as last, so the final node of
+    /// the body is never zero and can't be confused with padding.
+    fn body_len(&self) -> usize {
+        (0..self.node_count()).rev()
+            .find(|&ptr| self.read_word(ptr) != 0)
+            .map_or(0, |ptr| ptr + 1)
     }
 
     /// Test if a string matching the given pattern exists in the trie.
@@ -129,7 +371,7 @@ impl PackedTrie {
     /// Uses '*' for wildcard matching.
     #[inline]
     pub fn test(&self, needle: &str) -> bool {
-        !self.search_impl(needle, DEFAULT_WILDCARD, false, true).is_empty()
+        !self.search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false, true).is_empty()
     }
 
     /// Test if a string matching the given prefix pattern exists in the trie.
@@ -137,126 +379,465 @@ impl PackedTrie {
     /// Uses '*' for wildcard matching.
     #[inline]
     pub fn test_pfx(&self, needle: &str) -> bool {
-        !self.search_impl(needle, DEFAULT_WILDCARD, true, true).is_empty()
+        !self.search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true, true).is_empty()
     }
 
     /// Find all words matching the given pattern in the trie.
     ///
-    /// Uses '*' for wildcard matching.
+    /// Uses '*' for wildcard matching. Words are listed in reverse
+    /// breadth-first order; use `search_with` to pick the order.
     #[inline]
     pub fn search(&self, needle: &str) -> LinkedList<String> {
-        self.search_impl(needle, DEFAULT_WILDCARD, false, false)
+        self.search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false, false)
     }
 
     /// Find all words that use the given pattern as a prefix.
     ///
-    /// Uses '*' for wildcard matching.
+    /// Uses '*' for wildcard matching. Words are listed in reverse
+    /// breadth-first order; use `search_with` to pick the order.
     #[inline]
     pub fn search_pfx(&self, needle: &str) -> LinkedList<String> {
-        self.search_impl(needle, DEFAULT_WILDCARD, true, false)
+        self.search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true, false)
+    }
+
+    /// Find words matching the given pattern, with the wildcard, paging and
+    /// order set by the options.
+    ///
+    /// The order decides which words a page holds:
+    ///
+    ///  * `SearchOrder::TrieOrder` lists words in trie order, as
+    ///    `search_iter` does. Tries from `TrieBuilder` keep their chars
+    ///    sorted, so this is lexicographic order; other tries follow the
+    ///    order of their char table.
+    ///  * `SearchOrder::BreadthFirst` lists shorter words first, and words
+    ///    of the same length in trie order. This only differs from trie
+    ///    order for prefix searches.
+    ///
+    /// The first `offset` matches are skipped, and at most `limit` are
+    /// returned. Either way the search stops as soon as the page is full.
+    pub fn search_with(&self, needle: &str, options: &SearchOptions) -> Vec<String> {
+        let pattern = self.needle_pattern(needle, options.wildcard);
+        let allowed = self.resolve_pattern(&pattern);
+        let refs = pattern.var_refs();
+        let limit = options.limit.unwrap_or(usize::MAX);
+        match options.order {
+            SearchOrder::TrieOrder =>
+                SearchIter::resolved(self, Cow::Owned(allowed), Cow::Owned(refs), options.prefix, 0)
+                    .skip(options.offset)
+                    .take(limit)
+                    .collect(),
+            SearchOrder::BreadthFirst => {
+                let wanted = options.limit.map(|limit| options.offset.saturating_add(limit));
+                self.search_bfs(&allowed, &refs, options.prefix, wanted)
+                    .into_iter()
+                    .skip(options.offset)
+                    .collect()
+            },
+        }
+    }
+
+    /// Lazily iterate over all words matching the given pattern.
+    ///
+    /// Unlike `search`, matches are produced one at a time as the trie is
+    /// walked depth-first, so callers can stop early without paying for the
+    /// rest of the traversal. Uses '*' for wildcard matching.
+    #[inline]
+    pub fn search_iter<'b>(&'b self, needle: &str) -> SearchIter<'b> {
+        SearchIter::new(self, self.needle_pattern(needle, DEFAULT_WILDCARD), false)
+    }
+
+    /// Lazily iterate over all words that use the given pattern as a prefix.
+    ///
+    /// Uses '*' for wildcard matching.
+    #[inline]
+    pub fn search_pfx_iter<'b>(&'b self, needle: &str) -> SearchIter<'b> {
+        SearchIter::new(self, self.needle_pattern(needle, DEFAULT_WILDCARD), true)
+    }
+
+    /// Version of the header the trie was loaded from.
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Whether the trie stores a score for each word.
+    #[inline]
+    pub fn has_scores(&self) -> bool {
+        self.score_mask != 0
+    }
+
+    /// Look up the score of a word, or `None` if the word is not in the
+    /// trie. Every word in a trie without scores scores 0.
+    pub fn score(&self, word: &str) -> Option<u32> {
+        let pattern = self.normalize(word).chars().map(CharSet::literal).collect();
+        SearchIter::new(self, pattern, false).next_scored().map(|(_, score)| score)
+    }
+
+    /// Find all words matching the given pattern that score at least
+    /// `min_score`, paired with their scores.
+    ///
+    /// Low-scoring subtrees are pruned during the traversal, so a high
+    /// minimum makes the search cheaper. Uses '*' for wildcard matching.
+    #[inline]
+    pub fn search_scored(&self, needle: &str, min_score: u32) -> Vec<(String, u32)> {
+        self.search_scored_iter(needle, min_score).collect()
+    }
+
+    /// Lazily iterate over all words matching the given pattern that score
+    /// at least `min_score`, paired with their scores.
+    ///
+    /// Uses '*' for wildcard matching.
+    #[inline]
+    pub fn search_scored_iter<'b>(&'b self, needle: &str, min_score: u32) -> ScoredSearchIter<'b> {
+        let pattern = self.needle_pattern(needle, DEFAULT_WILDCARD);
+        ScoredSearchIter { inner: SearchIter::with_min_score(self, pattern, false, min_score) }
+    }
+
+    /// Find all words matching the given per-position pattern that score at
+    /// least `min_score`, paired with their scores.
+    pub fn search_pattern_scored(&self, pattern: &Pattern, min_score: u32) -> Vec<(String, u32)> {
+        let inner = SearchIter::with_min_score(self, pattern.clone(), false, min_score);
+        ScoredSearchIter { inner }.collect()
+    }
+
+    /// Test if a word matching the given per-position pattern exists.
+    #[inline]
+    pub fn test_pattern(&self, pattern: &Pattern) -> bool {
+        !self.search_impl(pattern, false, true).is_empty()
+    }
+
+    /// Find all words matching the given per-position pattern.
+    #[inline]
+    pub fn search_pattern(&self, pattern: &Pattern) -> LinkedList<String> {
+        self.search_impl(pattern, false, false)
+    }
+
+    /// Count the words matching the given pattern without building them.
+    ///
+    /// Uses '*' for wildcard matching.
+    #[inline]
+    pub fn count(&self, needle: &str) -> usize {
+        self.count_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false)
+    }
+
+    /// Count the words that use the given pattern as a prefix.
+    ///
+    /// Uses '*' for wildcard matching.
+    #[inline]
+    pub fn count_pfx(&self, needle: &str) -> usize {
+        self.count_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true)
+    }
+
+    /// Count the words matching the given per-position pattern.
+    #[inline]
+    pub fn count_pattern(&self, pattern: &Pattern) -> usize {
+        self.count_impl(pattern, false)
+    }
+
+    /// The fully-qualified count method.
+    ///
+    /// Walks the trie like `search_impl`, but never materializes words. Once
+    /// the rest of the pattern is all wildcards, the subtree's word count is
+    /// looked up directly if every word below it has the right length.
+    fn count_impl(&self, pattern: &Pattern, prefix: bool) -> usize {
+        // An empty trie has no root level to search.
+        if self.root().is_none() {
+            return 0;
+        }
+        // Subtree counts can't tell which words satisfy the variables, so
+        // those have to be walked.
+        if pattern.has_vars() {
+            return SearchIter::new(self, pattern.clone(), prefix).count();
+        }
+        let allowed = self.resolve_pattern(pattern);
+        self.count_level(0, 0, &allowed, wild_suffix_start(pattern), prefix)
+    }
+
+    /// Count matches in the level starting at the given pointer.
+    ///
+    /// `wild_from` is the position from which the pattern is only wildcards
+    /// (see `wild_suffix_start`).
+    pub(crate) fn count_level(&self,
+                   pointer: usize,
+                   depth: usize,
+                   allowed: &[Vec<bool>],
+                   wild_from: usize,
+                   prefix: bool) -> usize {
+        let last_depth = allowed.len();
+
+        if depth >= wild_from {
+            let stats = self.level_stats()[pointer];
+            let remaining = last_depth - depth;
+            let min_len = stats.min_len as usize;
+            let max_len = stats.max_len as usize;
+            // Prefix matches need at least `remaining` more chars; exact
+            // matches need exactly that many.
+            let (none, all) = if prefix {
+                (max_len < remaining, min_len >= remaining)
+            } else {
+                (remaining < min_len || remaining > max_len,
+                 min_len == remaining && max_len == remaining)
+            };
+            if stats.count == 0 || none {
+                return 0;
+            }
+            if all {
+                return stats.count as usize;
+            }
+        }
+
+        let mut total = 0;
+        let mut word_ptr = pointer;
+        loop {
+            let word = self.read_node(word_ptr);
+            if word.char_idx == 0 {
+                if depth == last_depth {
+                    total += 1;
+                }
+            } else if depth < last_depth && allowed[depth][word.char_idx as usize] {
+                total += self.count_level(word.next, depth + 1, allowed, wild_from, prefix);
+            }
+            if word.last {
+                break;
+            }
+            word_ptr += 1;
+        }
+        total
+    }
+
+    /// Tally the letters at one position across all words matching the
+    /// pattern, without materializing the words.
+    ///
+    /// Panics if the position is outside the pattern.
+    pub fn letter_histogram(&self, pattern: &Pattern, position: usize) -> LetterHistogram {
+        assert!(position < pattern.len(), "Position {} is outside the pattern.", position);
+        let mut wanted = vec![false; pattern.len()];
+        wanted[position] = true;
+        self.histograms_impl(pattern, &wanted).swap_remove(position).unwrap()
+    }
+
+    /// Tally the letters at every open position of the pattern in a single
+    /// walk. Positions fixed to a single literal char get `None`.
+    pub fn letter_histograms(&self, pattern: &Pattern) -> Vec<Option<LetterHistogram>> {
+        let wanted: Vec<bool> = pattern.sets().iter()
+            .map(|set| match *set {
+                CharSet::OneOf(ref chars) => chars.len() != 1,
+                _ => true,
+            })
+            .collect();
+        self.histograms_impl(pattern, &wanted)
+    }
+
+    /// Compute letter histograms for the positions flagged in `wanted`.
+    fn histograms_impl(&self, pattern: &Pattern, wanted: &[bool]) -> Vec<Option<LetterHistogram>> {
+        let chars: Vec<char> = (0..self.inverse_table.len() as u32)
+            .map(|idx| self.inverse_table[&idx])
+            .collect();
+        let mut counts: Vec<Option<Vec<u32>>> = wanted.iter()
+            .map(|&w| if w { Some(vec![0; chars.len()]) } else { None })
+            .collect();
+
+        if pattern.has_vars() {
+            // Subtree counts can't tell which words satisfy the variables,
+            // so those have to be walked.
+            for word in SearchIter::new(self, pattern.clone(), false) {
+                for (depth, c) in word.chars().enumerate() {
+                    if let Some(ref mut hist) = counts[depth] {
+                        hist[self.char_index(c).unwrap() as usize] += 1;
+                    }
+                }
+            }
+        } else if self.root().is_some() {
+            let allowed = self.resolve_pattern(pattern);
+            let wild_from = wild_suffix_start(pattern);
+            // Below the deepest wanted position only counts are needed.
+            let tally_until = wanted.iter().rposition(|&w| w).map_or(0, |i| i + 1);
+            self.histogram_level(0, 0, &allowed, wild_from, tally_until, &mut counts);
+        }
+
+        counts.into_iter()
+            .map(|c| c.map(|counts| LetterHistogram { chars: chars.clone(), counts }))
+            .collect()
+    }
+
+    /// Count matches in the level at the given pointer, adding the number of
+    /// matches through each child to the histogram for its depth.
+    fn histogram_level(&self,
+                       pointer: usize,
+                       depth: usize,
+                       allowed: &[Vec<bool>],
+                       wild_from: usize,
+                       tally_until: usize,
+                       counts: &mut [Option<Vec<u32>>]) -> usize {
+        if depth >= tally_until {
+            return self.count_level(pointer, depth, allowed, wild_from, false);
+        }
+
+        let mut total = 0;
+        let mut word_ptr = pointer;
+        loop {
+            let word = self.read_node(word_ptr);
+            let idx = word.char_idx as usize;
+            if word.char_idx != 0 && allowed[depth][idx] {
+                let sub = self.histogram_level(word.next, depth + 1, allowed,
+                                               wild_from, tally_until, counts);
+                if let Some(ref mut hist) = counts[depth] {
+                    hist[idx] += sub as u32;
+                }
+                total += sub;
+            }
+            if word.last {
+                break;
+            }
+            word_ptr += 1;
+        }
+        total
+    }
+
+    /// Subtree statistics for every level, indexed by the pointer to the
+    /// level's first node. Computed in one pass over the trie on first use.
+    pub(crate) fn level_stats(&self) -> &[LevelStats] {
+        self.stats.get_or_init(|| self.compute_level_stats())
+    }
+
+    /// Compute statistics for every level reachable from the root.
+    ///
+    /// Walks the levels depth-first like `verify`, with an explicit stack so
+    /// that a deep body can't overflow the call stack. A level pointing back
+    /// to one on its own path (which `verify` rejects) counts as empty there.
+    fn compute_level_stats(&self) -> Vec<LevelStats> {
+        let empty = LevelStats { count: 0, min_len: 0, max_len: 0 };
+        let len = self.node_count();
+        let mut stats: Vec<Option<LevelStats>> = vec![None; len];
+        let root = match self.root() {
+            Some(root) => root,
+            None => return vec![empty; len],
+        };
+        let mut open = vec![false; len];
+        let start = LevelStats { count: 0, min_len: u16::MAX, max_len: 0 };
+        open[root] = true;
+        let mut stack = vec![StatsFrame { level: root, cursor: root, done: false, acc: start }];
+
+        while let Some(frame) = stack.last_mut() {
+            if frame.done {
+                let StatsFrame { level, acc, .. } = stack.pop().unwrap();
+                open[level] = false;
+                stats[level] = Some(acc);
+                if let Some(parent) = stack.last_mut() {
+                    parent.acc.add_child(acc);
+                }
+                continue;
+            }
+
+            let word = self.read_node(frame.cursor);
+            frame.cursor += 1;
+            frame.done = word.last;
+
+            if word.char_idx == 0 {
+                frame.acc.count = frame.acc.count.saturating_add(1);
+                frame.acc.min_len = 0;
+                continue;
+            }
+            if let Some(child) = stats[word.next] {
+                frame.acc.add_child(child);
+            } else if !open[word.next] {
+                open[word.next] = true;
+                stack.push(StatsFrame { level: word.next, cursor: word.next, done: false, acc: start });
+            }
+        }
+
+        stats.into_iter().map(|s| s.unwrap_or(empty)).collect()
     }
 
     /// The fully-qualified search method.
     ///
-    /// Implements wildcard and prefix matching.
+    /// Implements char set and prefix matching. Children whose chars are not
+    /// in the set for their position are pruned without being expanded.
+    /// Matches are listed in reverse breadth-first order.
     #[inline]
     fn search_impl(&self,
-                   needle: &str,
-                   wildcard: &char,
+                   pattern: &Pattern,
                    prefix: bool,
                    first: bool) -> LinkedList<String> {
+        let limit = if first { Some(1) } else { None };
         let mut matches: LinkedList<String> = LinkedList::new();
+        let allowed = self.resolve_pattern(pattern);
+        for word in self.search_bfs(&allowed, &pattern.var_refs(), prefix, limit) {
+            matches.push_front(word);
+        }
+        matches
+    }
 
-        // Convert the needle to a vector of chars for indexed access.
-        let char_vec: Vec<char> = needle.chars().collect();
+    /// Breadth-first search, returning matches in the order they are found:
+    /// shorter words first, and words of the same length in trie order.
+    /// Stops once `limit` matches are found.
+    ///
+    /// Chars are checked against the pattern's variables (`refs`) as they
+    /// are added, so partial words that break them aren't expanded.
+    pub(crate) fn search_bfs(&self,
+                             allowed: &[Vec<bool>],
+                             refs: &[Option<VarRef>],
+                             prefix: bool,
+                             limit: Option<usize>) -> Vec<String> {
+        let mut matches = Vec::new();
+
+        // An empty trie has no root level to search.
+        if self.root().is_none() || limit == Some(0) {
+            return matches;
+        }
 
         // Initialize search queue with a pointer to the root node of the trie.
-        let mut queue: LinkedList<SearchNode> = LinkedList::new();
-        queue.push_front(SearchNode {
+        let mut queue: VecDeque<SearchNode> = VecDeque::new();
+        queue.push_back(SearchNode {
             pointer: 0,
             memo: String::from(""),
             depth: 0,
         });
 
-        let last_depth = needle.len();
+        let last_depth = allowed.len();
 
         // Do BFS over trie to pull matches
-        while !queue.is_empty() {
-            let node = queue.pop_front().unwrap();
+        while let Some(node) = queue.pop_front() {
             let is_last = node.depth >= last_depth;
-            // Get the token to match, using a special terminal for the last.
-            let token = if is_last { TERMINAL } else { char_vec[node.depth] };
-            // This may be an explicit wildcard token, or an implicit one
-            // if it's a prefix search and we're beyond the last depth.
-            let is_wild = token == *wildcard || (prefix && is_last);
-            // Optimization: quit the search immediately if this is not a
-            // wildcard search and the token is not in the char table.
-            if !is_wild && !self.table.contains_key(&token) {
-                continue;
-            }
-            // Resolve the token's index from the char table to compare
-            // against blocks in the trie.
-            let tok_idx = if is_wild {
-                0u32
-            } else {
-                *self.table.get(&token).unwrap()
-            };
+            // Get the set to match. Past the end of the pattern only the
+            // terminal matches, unless this is a prefix search.
+            let set = allowed.get(node.depth);
 
             let mut word_ptr = node.pointer;
 
             loop {
-
-
                 // Extract the word.
-                // TODO(jnu) probably can replace mult with add on each iter.
-                let start_bit = word_ptr * self.word_width;
-                let word = get_bitvec_field(&self.data,
-                                            start_bit,
-                                            self.word_width);
-                let char_idx = (word >> self.char_shift) & self.char_mask;
-
-                // Test if the word is a match.
-                if is_wild || char_idx == tok_idx {
-                    // Add next pointer to search queue.
-                    let next_ptr = (word >> PTR_SHIFT) & self.pointer_mask;
-                    // Resolve the true next character (not necessarily the same
-                    // as the tok_index if this was a wildcard search).
-                    let new_char = *self.inverse_table.get(&char_idx).unwrap();
+                let word = self.read_node(word_ptr);
+
+                if word.char_idx == 0 {
                     // Stopping condition: searching last block and hit a terminal.
-                    if is_last && new_char == TERMINAL {
+                    if is_last {
                         // Save this match.
-                        matches.push_front(node.memo.clone());
-                        // Optimization: early exit if we only need first match
-                        if first {
+                        matches.push(node.memo.clone());
+                        // Optimization: early exit once the limit is reached
+                        if limit == Some(matches.len()) {
                             return matches;
                         }
-                        // If we are matching a literal, break out of the
-                        // inner loop now.
-                        if !is_wild {
-                            break;
-                        }
                     }
-
-                    // Push a new node into the search queue if it's non-terminal.
-                    if new_char != TERMINAL {
+                } else if set.map_or(prefix, |set| set[word.char_idx as usize]) {
+                    // Push a new node into the search queue if its char is
+                    // allowed at this position. Resolve the true next char,
+                    // and skip it if it breaks a variable.
+                    let new_char = *self.inverse_table.get(&word.char_idx).unwrap();
+                    if vars_allow(refs, node.depth, &node.memo, new_char) {
                         let mut new_memo = node.memo.clone();
                         new_memo.push(new_char);
                         queue.push_back(SearchNode {
-                            // XXX(jnu) there is an invariant here about the
-                            // relative magnitudes of offset and next_ptr,
-                            // i.e.  that |offset| < |next_ptr|. The casts are
-                            // a little sketchy.
-                            pointer: word_ptr + (self.offset + (next_ptr as i32)) as usize,
+                            pointer: word.next,
                             depth: node.depth + 1,
                             memo: new_memo,
                         })
                     }
-
                 }
 
-                // Handle non-matches. First, check if this was the last
-                // word in the level. If so, break now.
-                if word & LAST_MASK == 1 {
+                // Check if this was the last word in the level. If so, break
+                // now.
+                if word.last {
                     break;
                 }
 
@@ -268,39 +849,323 @@ impl PackedTrie {
         matches
     }
 
+    /// Resolve each position of a pattern to a membership table indexed by
+    /// char index, so nodes can be tested without decoding their chars. The
+    /// terminal is never a member.
+    pub(crate) fn resolve_pattern(&self, pattern: &Pattern) -> Vec<Vec<bool>> {
+        pattern.sets().iter()
+            .map(|set| {
+                let mut allowed = vec![false; self.inverse_table.len()];
+                match *set {
+                    CharSet::OneOf(ref chars) => {
+                        for c in chars {
+                            if let Some(&idx) = self.table.get(c) {
+                                allowed[idx as usize] = true;
+                            }
+                        }
+                    },
+                    _ => {
+                        for (&idx, &c) in &self.inverse_table {
+                            allowed[idx as usize] = set.contains(c);
+                        }
+                    },
+                }
+                allowed[0] = false;
+                allowed
+            })
+            .collect()
+    }
+
+    /// Pointer to the root level, or `None` if the trie has no body.
+    #[inline]
+    pub(crate) fn root(&self) -> Option<usize> {
+        if self.node_count() == 0 { None } else { Some(0) }
+    }
+
+    /// Number of nodes in the trie body, including any unreachable padding.
+    #[inline]
+    pub(crate) fn node_count(&self) -> usize {
+        64 * self.data.len() / self.word_width
+    }
+
+    /// Number of chars in the char table, not counting the terminal.
+    #[inline]
+    pub(crate) fn char_count(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    /// Resolve a char to its char table index, if the trie contains it.
+    #[inline]
+    pub(crate) fn char_index(&self, c: char) -> Option<u32> {
+        if c == TERMINAL { None } else { self.table.get(&c).cloned() }
+    }
+
+    /// Resolve a char table index to its char.
+    #[inline]
+    pub(crate) fn char_at(&self, char_idx: u32) -> char {
+        *self.inverse_table.get(&char_idx).unwrap()
+    }
+
+    /// Position of a char table index when the chars are sorted, with the
+    /// terminal before every char.
+    #[inline]
+    pub(crate) fn char_order(&self, char_idx: u32) -> u32 {
+        self.char_order[char_idx as usize]
+    }
+
+    /// Decode the node (word) at the given index in the trie body.
+    #[inline]
+    pub(crate) fn read_node(&self, ptr: usize) -> Node {
+        let word = self.read_word(ptr);
+        let next_ptr = (word >> PTR_SHIFT) & self.pointer_mask;
+        Node {
+            char_idx: ((word >> self.char_shift) & self.char_mask) as u32,
+            // XXX(jnu) there is an invariant here about the relative
+            // magnitudes of offset and next_ptr, i.e.  that
+            // |offset| < |next_ptr|. The casts are a little sketchy. Jumps
+            // may be negative when a DAWG level is shared with an earlier
+            // parent. A negative target wraps to a huge pointer, which
+            // `verify` reports as out of bounds.
+            next: (ptr as i64 + self.offset as i64 + next_ptr as i64) as usize,
+            last: word & LAST_MASK as u64 == 1,
+            score: ((word >> self.score_shift) & self.score_mask) as u32,
+        }
+    }
+
+    /// Read the raw bits of the node at the given index in the trie body.
+    #[inline]
+    fn read_word(&self, ptr: usize) -> u64 {
+        // TODO(jnu) probably can replace mult with add on each iter.
+        get_words_field(&self.data, ptr * self.word_width, self.word_width)
+    }
+
 }
 
 
+// Letter histograms --------------------------------------------------------
+
+/// Number of matching words with each letter at a given pattern position.
+///
+/// Counts are stored by char table index, so lookups by char are only as
+/// fast as a scan of the (small) table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LetterHistogram {
+    chars: Vec<char>,
+    counts: Vec<u32>,
+}
+
+
+impl LetterHistogram {
+
+    /// Number of matches with the given letter at the position.
+    pub fn get(&self, c: char) -> u32 {
+        self.chars.iter()
+            .position(|&x| x == c)
+            .map_or(0, |idx| self.counts[idx])
+    }
+
+    /// Total number of matches, i.e. the sum over all letters.
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Iterate over letters with a non-zero count, in char table order.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (char, u32)> + 'a {
+        self.chars.iter().cloned()
+            .zip(self.counts.iter().cloned())
+            .filter(|&(_, n)| n > 0)
+    }
+
+}
+
+
+
+// Lazy search iterator -----------------------------------------------------
+
+/// Position within a level on the DFS stack.
+struct Frame {
+    /// Index of the next node to examine in the level.
+    pointer: usize,
+    /// Whether the last node in the level has been examined.
+    done: bool,
+}
 
-// Private support methods --------------------------------------------------
 
-/// Create a bitvec with the base64-encoded binary content.
-// TODO(jnu) extend bitvec with this?
-fn bit_vec_from_base64(base64: &str) -> bit_vec::BitVec {
-    let len: usize = 6 * base64.len();
-    let mut v = bit_vec::BitVec::from_elem(len, false);
-    let mut i: usize = 0;
-    for c in base64.chars() {
-        let num = char_to_int(&c);
-        // Transfer bits to vec.
-        // TODO(jnu) how does rustc optimize this loop?
-        for j in 0..6 {
-            v.set(i, ((num >> (5 - j)) & 0x1u32) == 0x1u32);
-            i += 1;
-        }
-    }
-    v
+/// Iterator over the words matching a pattern, produced by a lazy DFS.
+///
+/// Words are yielded in trie order. For tries produced by `TrieBuilder`
+/// this is lexicographic order.
+pub struct SearchIter<'a> {
+    trie: &'a PackedTrie<'a>,
+    allowed: Cow<'a, [Vec<bool>]>,
+    refs: Cow<'a, [Option<VarRef>]>,
+    prefix: bool,
+    min_score: u32,
+    stack: Vec<Frame>,
+    memo: String,
 }
 
 
+impl<'a> SearchIter<'a> {
+
+    pub(crate) fn new(trie: &'a PackedTrie<'a>, pattern: Pattern, prefix: bool) -> SearchIter<'a> {
+        SearchIter::with_min_score(trie, pattern, prefix, 0)
+    }
+
+    fn with_min_score(trie: &'a PackedTrie<'a>,
+                      pattern: Pattern,
+                      prefix: bool,
+                      min_score: u32) -> SearchIter<'a> {
+        let allowed = Cow::Owned(trie.resolve_pattern(&pattern));
+        SearchIter::resolved(trie, allowed, Cow::Owned(pattern.var_refs()), prefix, min_score)
+    }
+
+    /// Search with a pattern already resolved against the trie (see
+    /// `PackedTrie::resolve_pattern` and `Pattern::var_refs`).
+    pub(crate) fn resolved(trie: &'a PackedTrie<'a>,
+                           allowed: Cow<'a, [Vec<bool>]>,
+                           refs: Cow<'a, [Option<VarRef>]>,
+                           prefix: bool,
+                           min_score: u32) -> SearchIter<'a> {
+        // An empty trie has no root level to search.
+        let mut stack = Vec::new();
+        if trie.root().is_some() {
+            stack.push(Frame { pointer: 0, done: false });
+        }
+
+        SearchIter {
+            trie,
+            allowed,
+            refs,
+            prefix,
+            min_score,
+            stack,
+            memo: String::new(),
+        }
+    }
+
+    /// Advance to the next match, returning it with its score.
+    ///
+    /// Nodes scoring below the minimum are skipped. Every node scores at
+    /// least as well as the words below it, so this prunes whole subtrees.
+    fn next_scored(&mut self) -> Option<(String, u32)> {
+        while !self.stack.is_empty() {
+            let depth = self.stack.len() - 1;
+            let frame = &mut self.stack[depth];
+
+            // Backtrack once every node in the level has been examined.
+            if frame.done {
+                self.stack.pop();
+                self.memo.pop();
+                continue;
+            }
+
+            let node = self.trie.read_node(frame.pointer);
+            frame.pointer += 1;
+            frame.done = node.last;
+
+            if node.score < self.min_score {
+                continue;
+            }
+
+            if node.char_idx == 0 {
+                if depth >= self.allowed.len() {
+                    return Some((self.memo.clone(), node.score));
+                }
+                continue;
+            }
+
+            // Beyond the end of the pattern only the terminal matches, unless
+            // this is a prefix search.
+            let allowed = match self.allowed.get(depth) {
+                Some(set) => set[node.char_idx as usize],
+                None => self.prefix,
+            };
+            if !allowed {
+                continue;
+            }
+
+            let c = *self.trie.inverse_table.get(&node.char_idx).unwrap();
+            if !vars_allow(&self.refs, depth, &self.memo, c) {
+                continue;
+            }
+            self.memo.push(c);
+            self.stack.push(Frame { pointer: node.next, done: false });
+        }
+
+        None
+    }
+
+}
+
+
+impl<'a> Iterator for SearchIter<'a> {
+    type Item = String;
+
+    #[inline]
+    fn next(&mut self) -> Option<String> {
+        self.next_scored().map(|(word, _)| word)
+    }
+}
+
+
+/// Iterator over the words matching a pattern paired with their scores.
+///
+/// Produced by the same lazy DFS as `SearchIter`, so words come in the same
+/// order.
+pub struct ScoredSearchIter<'a> {
+    inner: SearchIter<'a>,
+}
+
+
+impl<'a> Iterator for ScoredSearchIter<'a> {
+    type Item = (String, u32);
+
+    #[inline]
+    fn next(&mut self) -> Option<(String, u32)> {
+        self.inner.next_scored()
+    }
+}
+
+
+impl FromStr for PackedTrie<'static> {
+    type Err = TrieLoadError;
+
+    fn from_str(packed: &str) -> Result<PackedTrie<'static>, TrieLoadError> {
+        PackedTrie::try_from_str(packed)
+    }
+}
+
+
+impl<'a> TryFrom<&'a str> for PackedTrie<'static> {
+    type Error = TrieLoadError;
+
+    fn try_from(packed: &'a str) -> Result<PackedTrie<'static>, TrieLoadError> {
+        PackedTrie::try_from_str(packed)
+    }
+}
+
+
+
+// Thread safety ------------------------------------------------------------
+
+/// Fails to compile unless the type can be sent and shared across threads.
+pub(crate) fn assert_send_sync<T: Send + Sync>() {}
+
+// The only state that changes after loading is the level stats, which are
+// written once behind a `OnceLock`.
+const _: fn() = assert_send_sync::<PackedTrie<'static>>;
+
+
+
+// Private support methods --------------------------------------------------
+
 /// Construct a map from character to numeric index from the raw char table string.
 fn build_char_table(raw: &str) -> HashMap<char, u32> {
     let mut tbl = HashMap::new();
     tbl.insert(TERMINAL, 0);
-    let mut i: u32 = 1;
-    for c in raw.chars() {
+    for (i, c) in (1u32..).zip(raw.chars()) {
         tbl.insert(c, i);
-        i += 1;
     }
     tbl
 }
@@ -310,64 +1175,84 @@ fn build_char_table(raw: &str) -> HashMap<char, u32> {
 fn build_inverse_char_table(raw: &str) -> HashMap<u32, char> {
     let mut tbl = HashMap::new();
     tbl.insert(0, TERMINAL);
-    let mut i: u32 = 1;
-    for c in raw.chars() {
+    for (i, c) in (1u32..).zip(raw.chars()) {
         tbl.insert(i, c);
-        i += 1;
     }
     tbl
 }
 
 
-/// Extract a window of bits from a base-64 encoded sequence.
-///
-/// This method reads binary from the encoded string directly. It is ported
-/// from the Typescript implementation, which uses it for every lookup due
-/// to lack of better bit-level tooling in that language.
-///
-/// Use this for non performance-sensitive operations; i.e., never use this
-/// for lookups in the trie. Use get_bitvec_field instead for reads that are
-/// about 25% faster.
-// TODO(jnu) rewrite header parsing to use bitvec, get rid of this function.
-#[inline]
-fn get_base64_field(base64: &str, start: usize, bit_length: usize) -> u32 {
-    let bytes: &[u8] = base64.as_bytes();
-    let start_char: usize = (start as f32 / 6.0).floor() as usize;
-    let start_bit_offset: usize = start % 6;
-    let end_bit: usize = start_bit_offset + bit_length;
-    let char_len: usize = (end_bit as f32 / 6.0).ceil() as usize;
-    let mask: u32 = (0x1 << bit_length as u32) - 1;
-
-    let mut chunk: u32 = 0;
-    for i in 0..char_len {
-        let idx: usize = start_char + i;
-        chunk <<= 6;
-        chunk |= char_to_int(&(bytes[idx] as char))
+/// Construct the sort position of every char index from the raw char table
+/// string. The terminal (index 0) sorts first, as a word sorts before its
+/// extensions.
+fn build_char_order(raw: &str) -> Vec<u32> {
+    let mut sorted: Vec<(char, usize)> = raw.chars().zip(1..).collect();
+    sorted.sort();
+    let mut order = vec![0; sorted.len() + 1];
+    for (position, &(_, idx)) in (1u32..).zip(&sorted) {
+        order[idx] = position;
     }
+    order
+}
+
+
+/// Position from which the pattern is only wildcards.
+pub(crate) fn wild_suffix_start(pattern: &Pattern) -> usize {
+    pattern.sets().iter()
+        .rposition(|set| *set != CharSet::Any)
+        .map_or(0, |i| i + 1)
+}
 
-    let right_pad = end_bit % 6;
-    if right_pad > 0 {
-        chunk >>= 6 - right_pad;
-    }
 
-    chunk & mask
+/// View body bytes as big-endian words, borrowing them in place if the
+/// slice is aligned and holds a whole number of words, and copying them into
+/// zero-padded words otherwise.
+fn words_from_bytes(bytes: &[u8]) -> Cow<'_, [u64]> {
+    // SAFETY: every bit pattern is a valid u64, so any aligned run of bytes
+    // can be read as words.
+    let (head, words, tail) = unsafe { bytes.align_to::<u64>() };
+    if head.is_empty() && tail.is_empty() {
+        return Cow::Borrowed(words);
+    }
+    Cow::Owned(bytes.chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_ne_bytes(word)
+        })
+        .collect())
 }
 
 
-/// Read a window of bits from a bitvec as a contiguous integer.
+/// Read a window of bits from big-endian words as a contiguous integer. Bits
+/// are numbered most-significant first.
 ///
-/// Behavior is undefined when the window size exceeds 32 bits.
+/// The window spans at most two words, so this is a couple of shifts rather
+/// than a loop over bits. Behavior is undefined unless the window is between
+/// 1 and 64 bits wide.
 #[inline]
-fn get_bitvec_field(vec: &bit_vec::BitVec, start: usize, length: usize) -> u32 {
-    let mut val: u32 = 0x0;
-    let mut shft: usize = length - 1;
-    for i in start..(start + length) {
-        let digit = (vec.get(i).unwrap() as u32) << shft;
-        val |= digit;
-        // Careful with overflows.
-        shft = if shft > 0 { shft - 1 } else { 0 };
-    }
-    val
+fn get_words_field(words: &[u64], start: usize, length: usize) -> u64 {
+    let idx = start / 64;
+    let shift = start % 64;
+    let mut chunk = u64::from_be(words[idx]) << shift;
+    if shift + length > 64 {
+        chunk |= u64::from_be(words[idx + 1]) >> (64 - shift);
+    }
+    chunk >> (64 - length)
+}
+
+
+/// Write a window of bits into big-endian words, the inverse of
+/// `get_words_field`. The window must be zeroed beforehand.
+#[inline]
+fn set_words_field(words: &mut [u64], start: usize, length: usize, value: u64) {
+    let idx = start / 64;
+    let shift = start % 64;
+    let chunk = value << (64 - length);
+    words[idx] = (u64::from_be(words[idx]) | chunk >> shift).to_be();
+    if shift + length > 64 {
+        words[idx + 1] = (u64::from_be(words[idx + 1]) | chunk << (64 - shift)).to_be();
+    }
 }
 
 
@@ -378,6 +1263,10 @@ fn get_bitvec_field(vec: &bit_vec::BitVec, start: usize, length: usize) -> u32 {
 mod tests {
     use super::*;
     use test::Bencher;
+    use ::tiny_trie::binary::base64_to_binary;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::constants::{SCORED_VERSION, VERSION};
+    use ::tiny_trie::fixtures::{BRODA_5, NYT_7};
 
 
     // PackedTrie -----------------------------------------------------------
@@ -387,6 +1276,7 @@ mod tests {
     fn test_init_packed_trie() {
         let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
 
+        assert_eq!(pt.version(), 0);
         assert_eq!(pt.offset, 1);
         assert_eq!(pt.table, build_char_table("fboarz"));
         assert_eq!(pt.inverse_table, build_inverse_char_table("fboarz"));
@@ -394,7 +1284,7 @@ mod tests {
         assert_eq!(pt.pointer_mask, 0b11);
         assert_eq!(pt.char_mask, 0b111);
         assert_eq!(pt.char_shift, 3);
-        assert_eq!(pt.data, bit_vec_from_base64("KTbjds1FDB"));
+        assert_eq!(pt.data, words_from_bytes(&base64::decode("KTbjds1FDB", 0).unwrap()));
     }
 
     // Instantiation. Check version.
@@ -404,8 +1294,125 @@ mod tests {
         PackedTrie::from("BD/wAABAwIfboarzKTbjds1FDB");
     }
 
+    // Fallible instantiation. Each kind of corruption maps to an error.
+    #[test]
+    fn test_try_from_str() {
+        assert!(PackedTrie::try_from_str("BAAAAABAwIfboarzKTbjds1FDB").is_ok());
+        assert!("BAAAAABAwIfboarzKTbjds1FDB".parse::<PackedTrie>().is_ok());
+        assert!(PackedTrie::try_from("BAAAAABAwIfboarzKTbjds1FDB").is_ok());
+
+        assert_eq!(PackedTrie::try_from_str("BAAAA").err(),
+                   Some(TrieLoadError::TruncatedHeader { len: 5, expected: 10 }));
+        assert_eq!(PackedTrie::try_from_str("BD/wAABAwIfboarzKTbjds1FDB").err(),
+                   Some(TrieLoadError::UnsupportedVersion { version: 1023 }));
+        assert_eq!(PackedTrie::try_from_str("BAAA!ABAwIfboarzKTbjds1FDB").err(),
+                   Some(TrieLoadError::InvalidBase64Symbol { symbol: '!', offset: 4 }));
+        assert_eq!(PackedTrie::try_from_str("BAAAAABAwIfboarzKTb~ds1FDB").err(),
+                   Some(TrieLoadError::InvalidBase64Symbol { symbol: '~', offset: 19 }));
+        assert_eq!(PackedTrie::try_from_str("CAAAAABAwIfboarz").err(),
+                   Some(TrieLoadError::HeaderWidthExceedsInput { header_width: 32, len: 16 }));
+        assert_eq!(PackedTrie::try_from_str("BIAAAABAwIfboarzyxKTbjds1FDB").err(),
+                   Some(TrieLoadError::CharTableOverflow { entries: 9, char_width: 3 }));
+    }
+
+    // Binary instantiation borrows the body and matches the Base64 form.
+    #[test]
+    fn test_from_bytes() {
+        let binary = base64_to_binary("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+        let pt = PackedTrie::from_bytes(&binary).unwrap();
+        if (binary.as_ptr() as usize).is_multiple_of(8) {
+            assert!(match pt.data { Cow::Borrowed(_) => true, Cow::Owned(_) => false });
+        }
+        assert_eq!(pt.offset, 1);
+        assert_eq!(pt.table, build_char_table("fboarz"));
+        assert_eq!(pt.word_width, 6);
+        assert!(pt.test("foo"));
+        assert_eq!(pt.search_pfx_iter("").collect::<Vec<String>>(), vec!["foo", "bar", "baz"]);
+
+        // Misaligned buffers are copied, but read the same.
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&binary);
+        let copy = PackedTrie::from_bytes(&shifted[1..]).unwrap();
+        assert_eq!(copy.data, pt.data);
+        assert_eq!(copy.search_pfx_iter("").count(), 3);
+
+        assert_eq!(PackedTrie::from_bytes(b"BAAAAABAwIfboarzKTbjds1FDB").err(),
+                   Some(TrieLoadError::InvalidMagic));
+        assert_eq!(PackedTrie::from_bytes(&binary[..8]).err(),
+                   Some(TrieLoadError::TruncatedHeader { len: 8, expected: 14 }));
+    }
+
+    // Re-encoding reproduces the input in either format.
+    #[test]
+    fn test_to_base64_to_bytes() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.to_base64(), "BAAAAABAwIfboarzKTbjds1FDB");
+        let binary = base64_to_binary("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+        assert_eq!(pt.to_bytes(), binary);
+        assert_eq!(PackedTrie::from_bytes(&binary).unwrap().to_base64(), "BAAAAABAwIfboarzKTbjds1FDB");
+
+        for packed in &[BRODA_5, NYT_7] {
+            let pt = PackedTrie::from(packed.trim());
+            assert_eq!(pt.to_base64(), packed.trim());
+            let bytes = pt.to_bytes();
+            assert_eq!(PackedTrie::from_bytes(&bytes).unwrap().to_base64(), packed.trim());
+        }
+
+        let mut builder = TrieBuilder::new();
+        builder.insert_scored("cat", 3);
+        builder.insert_scored("cab", 9);
+        let pt = builder.build();
+        assert_eq!(pt.to_base64(), builder.encode());
+        assert_eq!(PackedTrie::from_bytes(&pt.to_bytes()).unwrap().score("cab"), Some(9));
+
+        let empty = TrieBuilder::new().build();
+        assert_eq!(PackedTrie::from(&empty.to_base64()).search_pfx(""), LinkedList::new());
+    }
+
+    // Re-encoding in another version keeps the words, and the scores where
+    // the version has them.
+    #[test]
+    fn test_to_version() {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let upgraded = pt.to_version(SCORED_VERSION).unwrap();
+        assert_eq!(upgraded.version(), SCORED_VERSION);
+        assert!(!upgraded.has_scores());
+        assert_eq!(upgraded.search("C*A*E"), pt.search("C*A*E"));
+        assert_eq!(upgraded.score("CRANE"), Some(0));
+        let reloaded = PackedTrie::from(&upgraded.to_base64());
+        assert_eq!(reloaded.version(), SCORED_VERSION);
+        assert_eq!(reloaded.count("*****"), 24033);
+
+        let mut builder = TrieBuilder::new();
+        builder.insert_scored("cat", 3);
+        builder.insert_scored("cab", 9);
+        builder.insert_scored("dog", 5);
+        let scored = builder.build();
+        let downgraded = scored.to_version(VERSION).unwrap();
+        assert_eq!(downgraded.version(), VERSION);
+        assert!(!downgraded.has_scores());
+        assert_eq!(downgraded.search_pfx(""), scored.search_pfx(""));
+        assert_eq!(PackedTrie::from(&downgraded.to_base64()).search_pfx(""), scored.search_pfx(""));
+        assert_eq!(downgraded.to_version(SCORED_VERSION).unwrap().score("cab"), Some(0));
+        assert_eq!(scored.to_version(SCORED_VERSION).unwrap().score("cab"), Some(9));
+
+        assert_eq!(pt.to_version(7).err(), Some(TrieLoadError::UnsupportedVersion { version: 7 }));
+    }
+
+    // Owned copies outlive the buffer they were loaded from.
+    #[test]
+    fn test_into_owned() {
+        let pt = {
+            let binary = base64_to_binary("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+            PackedTrie::from_bytes(&binary).unwrap().into_owned()
+        };
+        assert!(pt.test("baz"));
+        assert_eq!(pt.to_base64(), "BAAAAABAwIfboarzKTbjds1FDB");
+    }
+
     // Test simple membership
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_packed_trie_test() {
         let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
 
@@ -457,14 +1464,97 @@ mod tests {
         assert_eq!(pt.search("****"), exp);
     }
 
+    // Test lazy search
+    #[test]
+    fn test_packed_trie_search_iter() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let words: Vec<String> = pt.search_iter("***").collect();
+        assert_eq!(words, vec!["foo", "bar", "baz"]);
+
+        let words: Vec<String> = pt.search_iter("ba*").collect();
+        assert_eq!(words, vec!["bar", "baz"]);
+
+        assert_eq!(pt.search_iter("***").take(1).collect::<Vec<String>>(), vec!["foo"]);
+        assert_eq!(pt.search_iter("baz").count(), 1);
+        assert_eq!(pt.search_iter("bao").count(), 0);
+        assert_eq!(pt.search_iter("bxz").count(), 0);
+        assert_eq!(pt.search_iter("**").count(), 0);
+        assert_eq!(pt.search_iter("****").count(), 0);
+    }
+
+    // Test lazy prefix search
+    #[test]
+    fn test_packed_trie_search_pfx_iter() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let words: Vec<String> = pt.search_pfx_iter("b").collect();
+        assert_eq!(words, vec!["bar", "baz"]);
+        assert_eq!(pt.search_pfx_iter("").count(), 3);
+        assert_eq!(pt.search_pfx_iter("*o").count(), 1);
+    }
+
+    // Search with options
+    #[test]
+    fn test_packed_trie_search_with() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["cats", "cab", "cat", "cb", "dog"]);
+        let pt = builder.build();
+
+        let options = SearchOptions::new();
+        assert_eq!(pt.search_with("ca*", &options), vec!["cab", "cat"]);
+
+        let options = SearchOptions { wildcard: '?', ..SearchOptions::new() };
+        assert_eq!(pt.search_with("?a?", &options), vec!["cab", "cat"]);
+        assert!(pt.search_with("*a*", &options).is_empty());
+
+        let dfs = SearchOptions { prefix: true, ..SearchOptions::new() };
+        assert_eq!(pt.search_with("c", &dfs), vec!["cab", "cat", "cats", "cb"]);
+        let bfs = SearchOptions { order: SearchOrder::BreadthFirst, ..dfs.clone() };
+        assert_eq!(pt.search_with("c", &bfs), vec!["cb", "cab", "cat", "cats"]);
+
+        // Pages
+        let page = SearchOptions { offset: 1, limit: Some(2), ..dfs.clone() };
+        assert_eq!(pt.search_with("c", &page), vec!["cat", "cats"]);
+        let page = SearchOptions { offset: 1, limit: Some(2), ..bfs.clone() };
+        assert_eq!(pt.search_with("c", &page), vec!["cab", "cat"]);
+        let page = SearchOptions { offset: 3, limit: Some(2), ..bfs.clone() };
+        assert_eq!(pt.search_with("c", &page), vec!["cats"]);
+        let page = SearchOptions { offset: 9, ..dfs.clone() };
+        assert!(pt.search_with("c", &page).is_empty());
+        let page = SearchOptions { limit: Some(0), ..bfs.clone() };
+        assert!(pt.search_with("c", &page).is_empty());
+    }
+
+    // Paging through a real list gives the same words as the lazy search.
+    #[test]
+    fn test_search_with_pages() {
+        let pt = PackedTrie::from(NYT_7.trim());
+        let all: Vec<String> = pt.search_iter("*A***E*").collect();
+        let mut paged = Vec::new();
+        for page in 0..4 {
+            let options = SearchOptions { offset: 100 * page, limit: Some(100), ..SearchOptions::new() };
+            paged.extend(pt.search_with("*A***E*", &options));
+        }
+        assert_eq!(paged, all);
+
+        let bfs = SearchOptions { order: SearchOrder::BreadthFirst, ..SearchOptions::new() };
+        assert_eq!(pt.search_with("*A***E*", &bfs), all);
+    }
+
     // Benchmarks
 
-    // Init is slower than ideal due to base64 junk. TODO(jnu) improve this.
+    // Init is slower than ideal due to base64 junk. Use the binary format
+    // where init time matters.
     #[bench]
     fn bench_packed_trie_init(b: &mut Bencher) {
         b.iter(|| PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB"))
     }
 
+    #[bench]
+    fn bench_packed_trie_init_from_bytes(b: &mut Bencher) {
+        let binary = base64_to_binary("BAAAAABAwIfboarzKTbjds1FDB").unwrap();
+        b.iter(|| PackedTrie::from_bytes(&binary).unwrap())
+    }
+
     // Hit perf depends on trie structure. Try to get a worst case.
     #[bench]
     fn bench_packed_trie_test_hit(b: &mut Bencher) {
@@ -503,63 +1593,369 @@ mod tests {
     }
 
 
-    // get_base64_field (bit extraction) ------------------------------------
+    // Char class patterns are pruned during the search.
+    #[test]
+    fn test_packed_trie_search_pattern() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let mut exp: LinkedList<String> = LinkedList::new();
+        exp.push_front(String::from("bar"));
+        exp.push_front(String::from("baz"));
+        assert_eq!(pt.search_pattern(&Pattern::parse("[bf]a*").unwrap()), exp);
+        assert_eq!(pt.search_pattern(&Pattern::parse("**[^o]").unwrap()), exp);
+
+        exp.clear();
+        exp.push_front(String::from("foo"));
+        assert_eq!(pt.search_pattern(&Pattern::parse("[fx][^a][o]").unwrap()), exp);
 
-    // Test basic accuracy of bit window extraction
+        exp.clear();
+        assert_eq!(pt.search_pattern(&Pattern::parse("[xy]**").unwrap()), exp);
+        assert_eq!(pt.search_pattern(&Pattern::parse("*[]*").unwrap()), exp);
+        assert_eq!(pt.search_pattern(&Pattern::parse("[bf]a").unwrap()), exp);
+
+        assert!(pt.test_pattern(&Pattern::parse("*[ao][rz]").unwrap()));
+        assert!(!pt.test_pattern(&Pattern::parse("*[ao]o*").unwrap()));
+    }
+
+    // Counts agree with the number of search results.
     #[test]
-    fn test_get_base64_field() {
-        let test_str = String::from("foo+");
-        // For reference, "foo+" encodes the binary:
-        // 0111 1110 1000 1010 0011 1110
-        assert_eq!(get_base64_field(&test_str, 0, 4), 7);
-        assert_eq!(get_base64_field(&test_str, 2, 4), 15);
-        assert_eq!(get_base64_field(&test_str, 8, 8), 138);
-        assert_eq!(get_base64_field(&test_str, 10, 13), 1311);
+    fn test_packed_trie_count() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.count("***"), 3);
+        assert_eq!(pt.count("ba*"), 2);
+        assert_eq!(pt.count("*a*"), 2);
+        assert_eq!(pt.count("foo"), 1);
+        assert_eq!(pt.count("bao"), 0);
+        assert_eq!(pt.count("**"), 0);
+        assert_eq!(pt.count("****"), 0);
+        assert_eq!(pt.count_pfx(""), 3);
+        assert_eq!(pt.count_pfx("b"), 2);
+        assert_eq!(pt.count_pfx("***"), 3);
+        assert_eq!(pt.count_pfx("****"), 0);
+        assert_eq!(pt.count_pattern(&Pattern::parse("[bf]*[^z]").unwrap()), 2);
     }
 
-    // Test panic when window size exceeds bounds
     #[test]
-    #[should_panic]
-    fn test_get_base64_field_bounds() {
-        let test_str = String::from("foo+");
-        get_base64_field(&test_str, 17, 8);
+    fn test_packed_trie_search_vars() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let vars = |pattern: &str| Pattern::parse(pattern).unwrap();
+        assert_eq!(pt.search_pattern(&vars("*11")).into_iter().collect::<Vec<String>>(), vec!["foo"]);
+        assert_eq!(pt.search_pattern(&vars("123")).len(), 2);
+        assert!(!pt.test_pattern(&vars("1*1")));
+        assert!(pt.test_pattern(&vars("b12")));
+        assert_eq!(pt.count_pattern(&vars("*11")), 1);
+        assert_eq!(pt.compile_pattern(&vars("*12")).unwrap().count(), 2);
+        assert_eq!(pt.compile_pattern(&vars("*11")).unwrap().run(), vec!["foo"]);
+        assert_eq!(pt.search_pattern(&vars("[bf]11")).len(), 1);
+
+        // Plain needles have no variables; their digits match themselves.
+        assert!(pt.search("*11").is_empty());
+        assert!(!pt.test_pfx("*1"));
+        assert_eq!(pt.count("*11"), 0);
+        assert!(pt.compile("*12").is_err());
+        assert!(pt.search_with("?11", &SearchOptions { wildcard: '?', ..SearchOptions::new() }).is_empty());
     }
 
-    // Test panic when starts out of bounds
+    // Histograms only tally the matches that satisfy the variables.
     #[test]
-    #[should_panic]
-    fn test_get_base64_field_oob_start() {
-        let test_str = String::from("foo+");
-        get_base64_field(&test_str, 25, 1);
+    fn test_letter_histograms_vars() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let hists = pt.letter_histograms(&Pattern::parse("*11").unwrap());
+        assert_eq!(hists[0].as_ref().unwrap().get('f'), 1);
+        assert_eq!(hists[0].as_ref().unwrap().get('b'), 0);
+        assert_eq!(hists[2].as_ref().unwrap().get('o'), 1);
+        assert_eq!(pt.letter_histogram(&Pattern::parse("b12").unwrap(), 2).get('z'), 1);
     }
 
+    // Counts on tries with words of mixed lengths.
+    #[test]
+    fn test_packed_trie_count_mixed_lengths() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["a", "ab", "abc", "b", "bcd", "cab"]);
+        let pt = builder.build();
+        for needle in &["*", "**", "***", "****", "a**", "*b", "*b*", "b**"] {
+            assert_eq!(pt.count(needle), pt.search(needle).len(), "{}", needle);
+        }
+        for needle in &["", "*", "**", "***", "a", "ab", "*b"] {
+            assert_eq!(pt.count_pfx(needle), pt.search_pfx(needle).len(), "{}", needle);
+        }
+        assert_eq!(TrieBuilder::new().build().count("*"), 0);
+    }
 
-    // get_bitvec_field (bit window extraction) -----------------------------
+    // Histograms tally the letters of matching words by position.
+    #[test]
+    fn test_packed_trie_letter_histogram() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let pattern = Pattern::parse("***").unwrap();
+
+        let hist = pt.letter_histogram(&pattern, 0);
+        assert_eq!(hist.get('b'), 2);
+        assert_eq!(hist.get('f'), 1);
+        assert_eq!(hist.get('z'), 0);
+        assert_eq!(hist.get('x'), 0);
+        assert_eq!(hist.total(), 3);
+
+        let hist = pt.letter_histogram(&pattern, 2);
+        assert_eq!(hist.iter().collect::<Vec<(char, u32)>>(),
+                   vec![('o', 1), ('r', 1), ('z', 1)]);
+
+        let hist = pt.letter_histogram(&Pattern::parse("b*[^z]").unwrap(), 1);
+        assert_eq!(hist.iter().collect::<Vec<(char, u32)>>(), vec![('a', 1)]);
 
+        assert_eq!(pt.letter_histogram(&Pattern::parse("x**").unwrap(), 1).total(), 0);
+    }
+
+    // All open positions are tallied in one walk.
     #[test]
-    fn test_get_bitvec_field() {
-        let test_str = String::from("foo+");
-        // For reference, "foo+" encodes the binary:
-        // 0111 1110 1000 1010 0011 1110
-        let bv = bit_vec_from_base64(&test_str);
-        assert_eq!(get_bitvec_field(&bv, 0, 4), 7);
-        assert_eq!(get_bitvec_field(&bv, 2, 4), 15);
-        assert_eq!(get_bitvec_field(&bv, 8, 8), 138);
-        assert_eq!(get_bitvec_field(&bv, 10, 13), 1311);
+    fn test_packed_trie_letter_histograms() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let hists = pt.letter_histograms(&Pattern::parse("b*[rz]").unwrap());
+        assert_eq!(hists.len(), 3);
+        assert!(hists[0].is_none());
+        assert_eq!(hists[1].as_ref().unwrap().get('a'), 2);
+        assert_eq!(hists[2].as_ref().unwrap().get('r'), 1);
+        assert_eq!(hists[2].as_ref().unwrap().get('z'), 1);
+    }
+
+    // Lazy searches only pay for the matches that are consumed.
+    #[bench]
+    fn bench_packed_trie_search_iter_first(b: &mut Bencher) {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        b.iter(|| pt.search_iter("***").next());
+    }
+
+    #[bench]
+    fn bench_packed_trie_search_iter_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        b.iter(|| pt.search_iter("***").count());
+    }
+
+
+    // Counting avoids building strings, and all-wildcard counts are a lookup.
+    #[bench]
+    fn bench_packed_trie_count_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        b.iter(|| pt.count("***"));
+    }
+
+    #[bench]
+    fn bench_packed_trie_count_partial_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        b.iter(|| pt.count("*a*"));
+    }
+
+
+    #[bench]
+    fn bench_packed_trie_letter_histograms(b: &mut Bencher) {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let pattern = Pattern::parse("***").unwrap();
+        b.iter(|| pt.letter_histograms(&pattern));
     }
 
 
-    // bit_vec_from_base64 (process binary) ---------------------------------
+    #[test]
+    fn test_packed_trie_search_scored() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec![("ARE", 40), ("EAR", 55), ("ERA", 20), ("ERR", 5), ("TEA", 60)]);
+        let pt = builder.build();
+
+        assert_eq!(pt.search_scored("E**", 0),
+                   vec![(String::from("EAR"), 55), (String::from("ERA"), 20),
+                        (String::from("ERR"), 5)]);
+        assert_eq!(pt.search_scored("***", 50),
+                   vec![(String::from("EAR"), 55), (String::from("TEA"), 60)]);
+        assert_eq!(pt.search_scored_iter("***", 61).count(), 0);
+        assert_eq!(pt.search_pattern_scored(&Pattern::parse("[AE]R*").unwrap(), 10),
+                   vec![(String::from("ARE"), 40), (String::from("ERA"), 20)]);
+
+        // Unscored tries read as scoring 0 everywhere.
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert!(!pt.has_scores());
+        assert_eq!(pt.score("foo"), Some(0));
+        assert_eq!(pt.search_scored("ba*", 0),
+                   vec![(String::from("bar"), 0), (String::from("baz"), 0)]);
+        assert!(pt.search_scored("ba*", 1).is_empty());
+    }
+
+    #[test]
+    fn test_from_bytes_scored() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec![("ARE", 40), ("EAR", 55), ("TEA", 60)]);
+        let binary = base64_to_binary(&builder.encode()).unwrap();
+        let pt = PackedTrie::from_bytes(&binary).unwrap();
+        assert_eq!(pt.version(), 1);
+        assert!(pt.has_scores());
+        assert_eq!(pt.search_scored("***", 50),
+                   vec![(String::from("EAR"), 55), (String::from("TEA"), 60)]);
+    }
+
+
+    // One trie serves searches from several threads, and its level stats
+    // are computed once whichever thread gets there first.
+    #[test]
+    fn test_shared_across_threads() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let counts: Vec<(usize, usize)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| (pt.search("***").len(), pt.count("ba*"))))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert_eq!(counts, vec![(3, 2); 4]);
+    }
+
+
+    // Real word lists ------------------------------------------------------
+
+    #[test]
+    fn test_real_word_lists() {
+        let broda = PackedTrie::from(BRODA_5.trim());
+        assert_eq!(broda.search("*****").len(), 24033);
+        assert_eq!(broda.search("C*A*E").len(), 18);
+        assert!(broda.test("CRANE"));
+
+        let nyt = PackedTrie::from(NYT_7.trim());
+        assert_eq!(nyt.search("*******").len(), 13197);
+        assert_eq!(nyt.search("*A***E*").len(), 396);
+        assert!(nyt.test("PARADOX"));
+        assert!(!nyt.test("QZQZQZQ"));
+
+        let binary = base64_to_binary(NYT_7.trim()).unwrap();
+        let nyt_bytes = PackedTrie::from_bytes(&binary).unwrap();
+        assert_eq!(nyt_bytes.search("*A***E*"), nyt.search("*A***E*"));
+    }
+
+    // Variables are checked during the traversal, but must find the same
+    // words as filtering the wildcard matches afterwards.
+    #[test]
+    fn test_real_word_list_vars() {
+        let broda = PackedTrie::from(BRODA_5.trim());
+        let words: Vec<Vec<char>> = broda.search_iter("*****").map(|w| w.chars().collect()).collect();
+        type Shape = fn(&[char]) -> bool;
+        let shapes: Vec<(&str, Shape)> = vec![
+            ("12321", |w| w[0] == w[4] && w[1] == w[3] && w[0] != w[1] && w[0] != w[2] && w[1] != w[2]),
+            ("1**1*", |w| w[0] == w[3]),
+            ("12*21", |w| w[0] == w[4] && w[1] == w[3] && w[0] != w[1]),
+            ("*1221", |w| w[1] == w[4] && w[2] == w[3] && w[1] != w[2]),
+        ];
+        for (needle, shape) in shapes {
+            let pattern = Pattern::parse(needle).unwrap();
+            let expected: Vec<String> = words.iter()
+                .filter(|w| shape(w))
+                .map(|w| w.iter().collect())
+                .collect();
+            assert!(!expected.is_empty(), "{}", needle);
+            assert_eq!(broda.compile_pattern(&pattern).unwrap().run(), expected, "{}", needle);
+            assert_eq!(broda.search_pattern(&pattern).len(), expected.len(), "{}", needle);
+            assert_eq!(broda.count_pattern(&pattern), expected.len(), "{}", needle);
+            let mut ids: Vec<u32> = expected.iter().map(|w| broda.rank(w).unwrap()).collect();
+            ids.sort();
+            assert_eq!(broda.search_pattern_ids(&pattern), ids, "{}", needle);
+        }
+        let radar = Pattern::parse("12321").unwrap();
+        assert!(broda.test_pattern(&radar));
+        assert!(broda.search_pattern(&radar).contains(&String::from("RADAR")));
+    }
+
+    // Plain needles match digits literally, so numbered entries in the
+    // list find only themselves.
+    #[test]
+    fn test_real_word_list_digits() {
+        let broda = PackedTrie::from(BRODA_5.trim());
+        assert_eq!(broda.search_iter("100TH").collect::<Vec<String>>(), vec!["100TH"]);
+        assert_eq!(broda.search("101ST").into_iter().collect::<Vec<String>>(), vec!["101ST"]);
+        assert_eq!(broda.count("10TO2"), 1);
+        assert!(broda.test("10TO2"));
+    }
+
+    #[bench]
+    fn bench_broda_init(b: &mut Bencher) {
+        b.iter(|| PackedTrie::from(BRODA_5.trim()))
+    }
+
+    #[bench]
+    fn bench_broda_init_from_bytes(b: &mut Bencher) {
+        let binary = base64_to_binary(BRODA_5.trim()).unwrap();
+        b.iter(|| PackedTrie::from_bytes(&binary).unwrap())
+    }
+
+    #[bench]
+    fn bench_broda_test_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        b.iter(|| pt.test("CRANE"));
+    }
+
+    #[bench]
+    fn bench_broda_search_vars(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let pattern = Pattern::parse("12*21").unwrap();
+        b.iter(|| pt.search_pattern(&pattern));
+    }
+
+    #[bench]
+    fn bench_broda_search_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        b.iter(|| pt.search("C*A*E"));
+    }
+
+    #[bench]
+    fn bench_broda_search_all(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        b.iter(|| pt.search_iter("*****").count());
+    }
+
+    #[bench]
+    fn bench_nyt_test_miss(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.test("QZQZQZQ"));
+    }
+
+    #[bench]
+    fn bench_nyt_search_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.search("*A***E*"));
+    }
+
+    #[bench]
+    fn bench_nyt_search_iter_wc_hit(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.search_iter("*A***E*").count());
+    }
+
+
+    // get_words_field (bit window extraction) ------------------------------
+
+    #[test]
+    fn test_get_words_field() {
+        // For reference, these bytes hold the binary:
+        // 0111 1110 1000 1010 0011 1110
+        let words = words_from_bytes(&[0x7e, 0x8a, 0x3e]);
+        assert_eq!(get_words_field(&words, 0, 4), 7);
+        assert_eq!(get_words_field(&words, 2, 4), 15);
+        assert_eq!(get_words_field(&words, 8, 8), 138);
+        assert_eq!(get_words_field(&words, 10, 13), 1311);
+        assert_eq!(get_words_field(&words, 0, 24), 0x7e8a3e);
+    }
+
+    #[test]
+    fn test_get_words_field_straddle() {
+        let mut bytes = vec![0u8; 16];
+        bytes[7] = 0x0f;
+        bytes[8] = 0xf0;
+        let words = words_from_bytes(&bytes);
+        assert_eq!(get_words_field(&words, 60, 8), 0xff);
+        assert_eq!(get_words_field(&words, 56, 16), 0x0ff0);
+        assert_eq!(get_words_field(&words, 62, 3), 0b111);
+    }
 
     #[test]
-    fn test_bit_vec_from_base64() {
-        // For reference, "fo" encodes the binary:
-        // 0111 1110 1000
-        let test_str = String::from("fo");
-
-        assert!(bit_vec_from_base64(&test_str).eq_vec(&[false, true, true, true,
-                                                                true, true, true, false,
-                                                                true, false, false, false]));
+    fn test_set_words_field() {
+        let mut words = vec![0u64; 2];
+        set_words_field(&mut words, 60, 8, 0xff);
+        set_words_field(&mut words, 0, 4, 0b1010);
+        assert_eq!(get_words_field(&words, 60, 8), 0xff);
+        assert_eq!(get_words_field(&words, 0, 4), 0b1010);
+        assert_eq!(get_words_field(&words, 56, 16), 0x0ff0);
+        assert_eq!(words, &*words_from_bytes(&[0xa0, 0, 0, 0, 0, 0, 0, 0x0f, 0xf0, 0, 0, 0, 0, 0, 0, 0]));
     }
 
 }
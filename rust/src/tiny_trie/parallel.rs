@@ -0,0 +1,137 @@
+//! Parallel searches, behind the `rayon` feature.
+//!
+//! A search is split across the branches of the root level, one task per
+//! first letter the pattern allows, and the results are joined back in trie
+//! order. Searches that fix the first letter have a single branch and gain
+//! nothing. The split is for leading wildcards on long lists, with spare
+//! cores to run the branches on; on a single core it only adds overhead.
+use std::borrow::Cow;
+use rayon::prelude::*;
+use ::tiny_trie::packed::{PackedTrie, SearchIter, DEFAULT_WILDCARD};
+use ::tiny_trie::pattern::Pattern;
+
+
+// Parallel search ----------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Find all words matching the given pattern, searching the branches
+    /// of the root level in parallel.
+    ///
+    /// Uses '*' for wildcard matching. Words are listed in trie order, as
+    /// `search_iter` lists them.
+    #[inline]
+    pub fn par_search(&self, needle: &str) -> Vec<String> {
+        self.par_search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false)
+    }
+
+    /// Find all words that use the given pattern as a prefix, searching the
+    /// branches of the root level in parallel.
+    ///
+    /// Uses '*' for wildcard matching.
+    #[inline]
+    pub fn par_search_pfx(&self, needle: &str) -> Vec<String> {
+        self.par_search_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true)
+    }
+
+    /// Find all words matching the given per-position pattern, searching the
+    /// branches of the root level in parallel.
+    #[inline]
+    pub fn par_search_pattern(&self, pattern: &Pattern) -> Vec<String> {
+        self.par_search_impl(pattern, false)
+    }
+
+    /// The fully-qualified parallel search method.
+    fn par_search_impl(&self, pattern: &Pattern, prefix: bool) -> Vec<String> {
+        let root = match self.root() {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        let allowed = self.resolve_pattern(pattern);
+        let refs = pattern.var_refs();
+
+        // Only the empty word ends at the root, so an empty pattern has no
+        // branches worth splitting.
+        if allowed.is_empty() {
+            return SearchIter::resolved(self, Cow::Owned(allowed), Cow::Owned(refs), prefix, 0)
+                .collect();
+        }
+
+        // Narrow the first position to each allowed branch in turn.
+        let mut branches = Vec::new();
+        let mut word_ptr = root;
+        loop {
+            let word = self.read_node(word_ptr);
+            if word.char_idx != 0 && allowed[0][word.char_idx as usize] {
+                let mut branch = allowed.clone();
+                branch[0] = vec![false; allowed[0].len()];
+                branch[0][word.char_idx as usize] = true;
+                branches.push(branch);
+            }
+            if word.last {
+                break;
+            }
+            word_ptr += 1;
+        }
+
+        branches.into_par_iter()
+            .map(|branch| {
+                SearchIter::resolved(self, Cow::Owned(branch), Cow::Borrowed(&refs), prefix, 0)
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>()
+            .concat()
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use test::Bencher;
+    use ::tiny_trie::fixtures::NYT_7;
+    use ::tiny_trie::packed::PackedTrie;
+    use ::tiny_trie::pattern::Pattern;
+
+    #[test]
+    fn test_par_search() {
+        // This trie lists `foo` first.
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.par_search("***"), vec!["foo", "bar", "baz"]);
+        assert_eq!(pt.par_search("*a*"), vec!["bar", "baz"]);
+        assert!(pt.par_search("*11").is_empty());
+        assert_eq!(pt.par_search_pattern(&Pattern::parse("*11").unwrap()), vec!["foo"]);
+        assert_eq!(pt.par_search_pfx("f"), vec!["foo"]);
+        assert_eq!(pt.par_search_pattern(&Pattern::parse("[bf]*[^z]").unwrap()), vec!["foo", "bar"]);
+        assert!(pt.par_search("****").is_empty());
+        assert!(pt.par_search("").is_empty());
+    }
+
+    // The parallel search finds the same words in the same order as the
+    // lazy one.
+    #[test]
+    fn test_par_search_real_word_list() {
+        let pt = PackedTrie::from(NYT_7.trim());
+        for needle in &["*******", "*A***E*", "P******"] {
+            assert_eq!(pt.par_search(needle), pt.search_iter(needle).collect::<Vec<String>>(), "{}", needle);
+        }
+        let pattern = Pattern::parse("1**1***").unwrap();
+        assert_eq!(pt.par_search_pattern(&pattern), pt.compile_pattern(&pattern).unwrap().run());
+    }
+
+    #[bench]
+    fn bench_nyt_search_all(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.search_iter("*******").collect::<Vec<String>>().len());
+    }
+
+    #[bench]
+    fn bench_nyt_par_search_all(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.par_search("*******").len());
+    }
+
+}
@@ -0,0 +1,476 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+use ::tiny_trie::error::PatternError;
+
+
+// Constants ----------------------------------------------------------------
+
+/// Wildcard matching any char in the bracket syntax.
+const WILDCARD: char = '*';
+
+/// Opens a char class in the bracket syntax.
+const CLASS_OPEN: char = '[';
+
+/// Closes a char class in the bracket syntax.
+const CLASS_CLOSE: char = ']';
+
+/// Negates a char class when it appears first inside the brackets.
+const CLASS_NEGATE: char = '^';
+
+/// Binds a char class to a variable when followed by a digit at the end of
+/// the brackets.
+const CLASS_VAR: char = '=';
+
+/// Makes the next char inside a char class a plain member.
+const CLASS_ESCAPE: char = '\\';
+
+/// Radix of the digits naming variables.
+const VAR_RADIX: u32 = 10;
+
+
+
+// Char sets ----------------------------------------------------------------
+
+/// The set of chars allowed at one position of a pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CharSet {
+    /// Any char matches.
+    Any,
+    /// Only the given chars match.
+    OneOf(BTreeSet<char>),
+    /// Any char except the given ones matches.
+    NoneOf(BTreeSet<char>),
+}
+
+
+impl CharSet {
+
+    /// Set matching only the given char.
+    pub fn literal(c: char) -> CharSet {
+        CharSet::OneOf(Some(c).into_iter().collect())
+    }
+
+    /// Set matching any of the given chars.
+    pub fn one_of<I: IntoIterator<Item = char>>(chars: I) -> CharSet {
+        CharSet::OneOf(chars.into_iter().collect())
+    }
+
+    /// Set matching any char except the given ones.
+    pub fn none_of<I: IntoIterator<Item = char>>(chars: I) -> CharSet {
+        CharSet::NoneOf(chars.into_iter().collect())
+    }
+
+    /// Test whether the char is a member of the set.
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        match *self {
+            CharSet::Any => true,
+            CharSet::OneOf(ref chars) => chars.contains(&c),
+            CharSet::NoneOf(ref chars) => !chars.contains(&c),
+        }
+    }
+
+    /// Write the set in the bracket syntax, bound to the given variable if
+    /// any. Sets other than `Any` are bracketed when bound, and so are
+    /// single chars that would otherwise read as syntax.
+    fn write(&self, f: &mut fmt::Formatter, var: Option<u8>) -> fmt::Result {
+        let (negate, chars) = match (self, var) {
+            (CharSet::Any, Some(var)) => return write!(f, "{}", var),
+            (CharSet::Any, None) => return write!(f, "{}", WILDCARD),
+            (CharSet::OneOf(chars), None) if chars.len() == 1 => {
+                let c = *chars.iter().next().unwrap();
+                if c != WILDCARD && c != CLASS_OPEN && c != CLASS_CLOSE && !c.is_digit(VAR_RADIX) {
+                    return write!(f, "{}", c);
+                }
+                (false, chars)
+            },
+            (CharSet::OneOf(chars), _) => (false, chars),
+            (CharSet::NoneOf(chars), _) => (true, chars),
+        };
+        write!(f, "{}", CLASS_OPEN)?;
+        if negate {
+            write!(f, "{}", CLASS_NEGATE)?;
+        }
+        for &c in chars {
+            if c == CLASS_CLOSE || c == CLASS_NEGATE || c == CLASS_VAR || c == CLASS_ESCAPE {
+                write!(f, "{}", CLASS_ESCAPE)?;
+            }
+            write!(f, "{}", c)?;
+        }
+        if let Some(var) = var {
+            write!(f, "{}{}", CLASS_VAR, var)?;
+        }
+        write!(f, "{}", CLASS_CLOSE)
+    }
+
+}
+
+
+impl fmt::Display for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+
+
+// Variables ----------------------------------------------------------------
+
+/// Constraint a variable puts on the char at one position, in terms of the
+/// chars at earlier positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VarRef {
+    /// The char must equal the one at the given position, where the same
+    /// variable first appeared.
+    Same(usize),
+    /// The variable appears for the first time, so the char must differ from
+    /// the ones at the given positions, where the other variables did.
+    Differs(Vec<usize>),
+}
+
+
+impl VarRef {
+
+    /// Test a char against the constraint, given the word so far.
+    #[inline]
+    pub(crate) fn allows(&self, memo: &str, c: char) -> bool {
+        match *self {
+            VarRef::Same(position) => memo.chars().nth(position) == Some(c),
+            VarRef::Differs(ref positions) => {
+                positions.iter().all(|&position| memo.chars().nth(position) != Some(c))
+            },
+        }
+    }
+
+}
+
+
+/// Test a char against the variable constraint of its position, if any.
+#[inline]
+pub(crate) fn vars_allow(refs: &[Option<VarRef>], depth: usize, memo: &str, c: char) -> bool {
+    match refs.get(depth) {
+        Some(Some(var_ref)) => var_ref.allows(memo, c),
+        _ => true,
+    }
+}
+
+
+
+// Patterns -----------------------------------------------------------------
+
+/// A fixed-length search pattern made of one `CharSet` per position.
+///
+/// Patterns can be built directly from sets, e.g. from the letters allowed
+/// by crossing slots, or parsed from a bracket syntax where `*` matches any
+/// char, `[AER]` matches one of the listed chars, and `[^Q]` matches any
+/// char except the listed ones. Any other char matches itself.
+///
+/// In the bracket syntax a position can also be bound to a numbered
+/// variable, written as a digit. Positions with the same variable must hold
+/// the same char, and positions with different variables must hold
+/// different chars, so `1**1*` finds words whose first and fourth letters
+/// match and `1221` finds `ABBA` shapes. A digit in brackets, like `[1]`,
+/// matches itself. A char class is bound to a variable by ending it with `=`
+/// and the digit, so `[AEIOU=1]*1` finds words whose first and third
+/// letters are the same vowel.
+///
+/// Inside brackets a backslash makes the next char a plain member, so
+/// `[\]]` matches `]` and `[\^]` matches `^`. `Display` writes patterns in
+/// this syntax, and parsing the result gives back the same pattern.
+///
+/// Plain needles, as taken by `PackedTrie::search` and the other `&str`
+/// methods, and globs (see the `glob` module) have no variables: a digit
+/// there matches itself, so word lists with entries like `100TH` can be
+/// searched as they are. Variables are opt-in, through `parse` or
+/// `with_var`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    sets: Vec<CharSet>,
+    /// Variable bound to each position, empty if there are none.
+    vars: Vec<Option<u8>>,
+}
+
+
+impl Pattern {
+
+    /// Create a pattern from per-position char sets.
+    pub fn new(sets: Vec<CharSet>) -> Pattern {
+        Pattern { sets, vars: Vec::new() }
+    }
+
+    /// Bind a position to a variable. The position still has to match its
+    /// char set as well.
+    ///
+    /// Panics if the position is outside the pattern, or if the variable
+    /// isn't a single digit.
+    pub fn with_var(mut self, position: usize, var: u8) -> Pattern {
+        assert!(position < self.sets.len(), "Position {} is outside the pattern.", position);
+        assert!((var as u32) < VAR_RADIX, "Variable {} is not a single digit.", var);
+        self.vars.resize(self.sets.len(), None);
+        self.vars[position] = Some(var);
+        self
+    }
+
+    /// Parse a pattern from the bracket syntax.
+    pub fn parse(pattern: &str) -> Result<Pattern, PatternError> {
+        let mut sets = Vec::new();
+        let mut vars = Vec::new();
+        let mut chars = pattern.char_indices();
+        while let Some((offset, c)) = chars.next() {
+            let mut var = c.to_digit(VAR_RADIX).map(|var| var as u8);
+            let set = match c {
+                WILDCARD => CharSet::Any,
+                _ if c.is_digit(VAR_RADIX) => CharSet::Any,
+                CLASS_CLOSE => return Err(PatternError::UnexpectedClassClose { offset }),
+                CLASS_OPEN => {
+                    // Members, each flagged if it was escaped.
+                    let mut members = Vec::new();
+                    let mut closed = false;
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            CLASS_CLOSE => {
+                                closed = true;
+                                break;
+                            },
+                            CLASS_ESCAPE => match chars.next() {
+                                Some((_, escaped)) => members.push((escaped, true)),
+                                None => break,
+                            },
+                            _ => members.push((c, false)),
+                        }
+                    }
+                    if !closed {
+                        return Err(PatternError::UnclosedClass { offset });
+                    }
+                    let negate = members.first() == Some(&(CLASS_NEGATE, false));
+                    if negate {
+                        members.remove(0);
+                    }
+                    if let [.., (CLASS_VAR, false), (digit, false)] = members[..] {
+                        if let Some(digit) = digit.to_digit(VAR_RADIX) {
+                            var = Some(digit as u8);
+                            members.truncate(members.len() - 2);
+                        }
+                    }
+                    let members = members.into_iter().map(|(c, _)| c).collect();
+                    if negate {
+                        CharSet::NoneOf(members)
+                    } else {
+                        CharSet::OneOf(members)
+                    }
+                },
+                _ => CharSet::literal(c),
+            };
+            vars.push(var);
+            sets.push(set);
+        }
+        Ok(Pattern::new(sets).with_vars(vars))
+    }
+
+    /// Create a pattern from a plain needle where the given wildcard matches
+    /// any char and every other char, digits included, matches itself.
+    pub fn from_wildcard(needle: &str, wildcard: char) -> Pattern {
+        needle.chars()
+            .map(|c| if c == wildcard { CharSet::Any } else { CharSet::literal(c) })
+            .collect()
+    }
+
+    /// Set the variables of all positions, dropping them if there are none.
+    fn with_vars(mut self, vars: Vec<Option<u8>>) -> Pattern {
+        if vars.iter().any(Option::is_some) {
+            self.vars = vars;
+        }
+        self
+    }
+
+    /// Number of positions in the pattern.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    /// Whether the pattern has no positions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// The char set for the given position, if it is within the pattern.
+    #[inline]
+    pub fn get(&self, position: usize) -> Option<&CharSet> {
+        self.sets.get(position)
+    }
+
+    /// The per-position char sets.
+    #[inline]
+    pub fn sets(&self) -> &[CharSet] {
+        &self.sets
+    }
+
+    /// The variable bound to the given position, if any.
+    #[inline]
+    pub fn var(&self, position: usize) -> Option<u8> {
+        self.vars.get(position).and_then(|&var| var)
+    }
+
+    /// Whether any position is bound to a variable.
+    #[inline]
+    pub fn has_vars(&self) -> bool {
+        !self.vars.is_empty()
+    }
+
+    /// Resolve the variables to constraints on each position, or an empty
+    /// list if there are none.
+    pub(crate) fn var_refs(&self) -> Vec<Option<VarRef>> {
+        let mut first: Vec<(u8, usize)> = Vec::new();
+        self.vars.iter().enumerate()
+            .map(|(position, &var)| {
+                let var = var?;
+                if let Some(&(_, earlier)) = first.iter().find(|&&(seen, _)| seen == var) {
+                    return Some(VarRef::Same(earlier));
+                }
+                let others = first.iter().map(|&(_, earlier)| earlier).collect();
+                first.push((var, position));
+                Some(VarRef::Differs(others))
+            })
+            .collect()
+    }
+
+}
+
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(pattern: &str) -> Result<Pattern, PatternError> {
+        Pattern::parse(pattern)
+    }
+}
+
+
+impl FromIterator<CharSet> for Pattern {
+    fn from_iter<I: IntoIterator<Item = CharSet>>(sets: I) -> Pattern {
+        Pattern::new(sets.into_iter().collect())
+    }
+}
+
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (position, set) in self.sets.iter().enumerate() {
+            set.write(f, self.var(position))?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_set_contains() {
+        assert!(CharSet::Any.contains('Q'));
+        assert!(CharSet::one_of("AER".chars()).contains('E'));
+        assert!(!CharSet::one_of("AER".chars()).contains('Q'));
+        assert!(CharSet::none_of("Q".chars()).contains('E'));
+        assert!(!CharSet::none_of("Q".chars()).contains('Q'));
+    }
+
+    #[test]
+    fn test_parse() {
+        let pattern = Pattern::parse("[AER]**[^Q]S").unwrap();
+        assert_eq!(pattern.sets(), &[CharSet::one_of("AER".chars()),
+                                     CharSet::Any,
+                                     CharSet::Any,
+                                     CharSet::none_of("Q".chars()),
+                                     CharSet::literal('S')]);
+        assert_eq!(pattern.len(), 5);
+        assert_eq!(Pattern::parse("").unwrap().len(), 0);
+        assert_eq!(Pattern::parse("[^]").unwrap().sets(), &[CharSet::none_of(None)]);
+        assert_eq!(Pattern::parse("[]").unwrap().sets(), &[CharSet::one_of(None)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Pattern::parse("A[BC").err(),
+                   Some(PatternError::UnclosedClass { offset: 1 }));
+        assert_eq!(Pattern::parse("AB]").err(),
+                   Some(PatternError::UnexpectedClassClose { offset: 2 }));
+    }
+
+    #[test]
+    fn test_from_wildcard() {
+        assert_eq!(Pattern::from_wildcard("B?[", '?').sets(),
+                   &[CharSet::literal('B'), CharSet::Any, CharSet::literal('[')]);
+    }
+
+    #[test]
+    fn test_parse_vars() {
+        let pattern = Pattern::parse("12*[AB]21").unwrap();
+        assert_eq!(pattern.len(), 6);
+        assert_eq!(pattern.get(0), Some(&CharSet::Any));
+        assert_eq!((0..6).map(|p| pattern.var(p)).collect::<Vec<_>>(),
+                   vec![Some(1), Some(2), None, None, Some(2), Some(1)]);
+        assert_eq!(pattern.var_refs(), vec![Some(VarRef::Differs(vec![])),
+                                            Some(VarRef::Differs(vec![0])),
+                                            None,
+                                            None,
+                                            Some(VarRef::Same(1)),
+                                            Some(VarRef::Same(0))]);
+        assert!(!Pattern::from_wildcard("1**1*", '*').has_vars());
+        assert_eq!(Pattern::from_wildcard("1**1*", '*'), Pattern::parse("[1]**[1]*").unwrap());
+        assert!(!Pattern::parse("[1]**").unwrap().has_vars());
+        assert_eq!(Pattern::parse("A*B").unwrap(), Pattern::new(vec![CharSet::literal('A'), CharSet::Any, CharSet::literal('B')]));
+        assert_eq!(Pattern::parse("A1").unwrap(), Pattern::parse("A*").unwrap().with_var(1, 1));
+    }
+
+    #[test]
+    fn test_var_ref_allows() {
+        assert!(VarRef::Same(1).allows("ABC", 'B'));
+        assert!(!VarRef::Same(1).allows("ABC", 'C'));
+        assert!(VarRef::Differs(vec![0, 2]).allows("ABC", 'B'));
+        assert!(!VarRef::Differs(vec![0, 2]).allows("ABC", 'C'));
+    }
+
+    #[test]
+    fn test_display() {
+        let pattern = Pattern::parse("[AER]**[^Q]S[*]").unwrap();
+        assert_eq!(pattern.to_string(), "[AER]**[^Q]S[*]");
+        assert_eq!(Pattern::parse("12*[AB]21").unwrap().to_string(), "12*[AB]21");
+        assert_eq!(Pattern::parse("[AB=1]*1").unwrap().to_string(), "[AB=1]*1");
+        assert_eq!(CharSet::one_of("]^".chars()).to_string(), "[\\]\\^]");
+    }
+
+    #[test]
+    fn test_parse_escapes_and_bound_classes() {
+        assert_eq!(Pattern::parse("[\\]]").unwrap().sets(), &[CharSet::literal(']')]);
+        assert_eq!(Pattern::parse("[\\^A]").unwrap().sets(), &[CharSet::one_of("^A".chars())]);
+        assert_eq!(Pattern::parse("[A\\=1]").unwrap(), Pattern::new(vec![CharSet::one_of("A=1".chars())]));
+        assert_eq!(Pattern::parse("[^Q=2]").unwrap(),
+                   Pattern::new(vec![CharSet::none_of("Q".chars())]).with_var(0, 2));
+        assert_eq!(Pattern::parse("[A\\").err(), Some(PatternError::UnclosedClass { offset: 0 }));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let patterns = vec![
+            Pattern::new(vec![CharSet::one_of("AB".chars()), CharSet::Any, CharSet::Any]).with_var(0, 1).with_var(2, 1),
+            Pattern::new(vec![CharSet::literal('Q'), CharSet::none_of("E".chars())]).with_var(0, 3).with_var(1, 4),
+            Pattern::new(vec![CharSet::literal(']'), CharSet::one_of("]\\".chars()), CharSet::none_of("]^=".chars())]),
+            Pattern::new(vec![CharSet::literal('^'), CharSet::literal('='), CharSet::literal('\\'), CharSet::literal('7')]),
+            Pattern::new(vec![CharSet::one_of(None), CharSet::none_of(None)]).with_var(1, 0),
+            Pattern::parse("[AER]*12[^Q]S[*]21").unwrap(),
+        ];
+        for p in patterns {
+            assert_eq!(Pattern::parse(&p.to_string()), Ok(p.clone()), "{}", p);
+        }
+    }
+
+}
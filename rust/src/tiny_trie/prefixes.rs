@@ -0,0 +1,102 @@
+use ::tiny_trie::packed::PackedTrie;
+
+
+// Prefix decomposition -----------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Find every word that is a prefix of the given text, shortest first.
+    ///
+    /// The text itself is included if it is a word.
+    pub fn prefixes_of(&self, text: &str) -> Vec<String> {
+        let text = self.normalize(text);
+        self.prefix_lengths(&text).into_iter()
+            .map(|len| text.chars().take(len).collect())
+            .collect()
+    }
+
+    /// Lengths in chars of the words that are prefixes of the text, which
+    /// must already be normalized, shortest first.
+    ///
+    /// Follows the text down a single path of the trie, noting each level
+    /// on the way that holds a terminal.
+    pub(crate) fn prefix_lengths(&self, text: &str) -> Vec<usize> {
+        let mut lengths = Vec::new();
+        let mut level = match self.root() {
+            Some(root) => root,
+            None => return lengths,
+        };
+
+        let mut chars = text.chars();
+        let mut depth = 0;
+        loop {
+            // A char missing from the table still lets this level end a word.
+            let wanted = chars.next().and_then(|c| self.char_index(c));
+            let mut next = None;
+            let mut word_ptr = level;
+            loop {
+                let word = self.read_node(word_ptr);
+                if word.char_idx == 0 {
+                    lengths.push(depth);
+                } else if Some(word.char_idx) == wanted {
+                    next = Some(word.next);
+                }
+                if word.last {
+                    break;
+                }
+                word_ptr += 1;
+            }
+
+            match next {
+                Some(next) => level = next,
+                None => return lengths,
+            }
+            depth += 1;
+        }
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::fixtures::NYT_7;
+    use ::tiny_trie::normalize::Normalizer;
+    use ::tiny_trie::packed::PackedTrie;
+
+    #[test]
+    fn test_prefixes_of() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["A", "AT", "ATE", "ATONE", "IT", "ITS", "TO"]);
+        let pt = builder.build();
+
+        assert_eq!(pt.prefixes_of("ATONEMENT"), vec!["A", "AT", "ATONE"]);
+        assert_eq!(pt.prefixes_of("ATE"), vec!["A", "AT", "ATE"]);
+        assert_eq!(pt.prefixes_of("ITSAWRAP"), vec!["IT", "ITS"]);
+        assert_eq!(pt.prefixes_of("A?"), vec!["A"]);
+        assert!(pt.prefixes_of("TAN").is_empty());
+        assert!(pt.prefixes_of("").is_empty());
+        assert!(TrieBuilder::new().build().prefixes_of("AT").is_empty());
+    }
+
+    #[test]
+    fn test_prefixes_of_normalized() {
+        let mut builder = TrieBuilder::new();
+        builder.set_normalizer(Normalizer::french());
+        builder.extend(vec!["cœur", "coeurs"]);
+        let pt = builder.build();
+        assert_eq!(pt.prefixes_of("Cœurs brisés"), vec!["COEUR", "COEURS"]);
+    }
+
+    #[bench]
+    fn bench_nyt_prefixes_of(b: &mut Bencher) {
+        let pt = PackedTrie::from(NYT_7.trim());
+        b.iter(|| pt.prefixes_of("PARADOXICAL"));
+    }
+
+}
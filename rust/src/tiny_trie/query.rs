@@ -0,0 +1,258 @@
+//! Search options, and queries compiled once against a trie and run many
+//! times.
+use std::borrow::Cow;
+use ::tiny_trie::error::QueryError;
+use ::tiny_trie::packed::{wild_suffix_start, PackedTrie, SearchIter, DEFAULT_WILDCARD};
+use ::tiny_trie::pattern::{CharSet, Pattern, VarRef};
+
+
+/// Order in which `PackedTrie::search_with` lists matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Trie order, from a depth-first walk. Lexicographic for tries built
+    /// with `TrieBuilder`.
+    TrieOrder,
+    /// Shorter words first, then trie order, from a breadth-first walk.
+    BreadthFirst,
+}
+
+
+/// Options for `PackedTrie::search_with`.
+///
+/// The defaults match `search_iter`: '*' is the wildcard, matches are exact
+/// and in trie order, and every match is returned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Char in the needle that matches any char.
+    pub wildcard: char,
+    /// Whether to match words that merely start with the needle.
+    pub prefix: bool,
+    /// Maximum number of matches to return.
+    pub limit: Option<usize>,
+    /// Number of matches to skip before the first one returned.
+    pub offset: usize,
+    /// Order in which matches are listed, and so which ones a page holds.
+    pub order: SearchOrder,
+}
+
+
+impl SearchOptions {
+
+    /// Create the default options.
+    pub fn new() -> SearchOptions {
+        SearchOptions {
+            wildcard: DEFAULT_WILDCARD,
+            prefix: false,
+            limit: None,
+            offset: 0,
+            order: SearchOrder::TrieOrder,
+        }
+    }
+
+}
+
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions::new()
+    }
+}
+
+
+/// A pattern resolved against a particular trie, ready to be run
+/// repeatedly.
+///
+/// Compiling does the per-query setup the search methods otherwise repeat
+/// on every call: each position is resolved to a membership table over the
+/// trie's char indices, and the trailing run of wildcards is located so
+/// counts can use subtree statistics. The query borrows the trie it was
+/// compiled for, since char indices mean nothing in any other trie.
+pub struct CompiledQuery<'a> {
+    trie: &'a PackedTrie<'a>,
+    /// Membership table for each position, indexed by char index.
+    allowed: Vec<Vec<bool>>,
+    /// Variable constraint for each position, empty if there are none.
+    refs: Vec<Option<VarRef>>,
+    /// Position from which the pattern is only wildcards.
+    wild_from: usize,
+    prefix: bool,
+    limit: Option<usize>,
+}
+
+
+impl<'a> CompiledQuery<'a> {
+
+    /// Stop after the given number of matches.
+    pub fn with_limit(mut self, limit: usize) -> CompiledQuery<'a> {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// The maximum number of matches to find, if any.
+    #[inline]
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Whether the query matches words that merely start with the pattern.
+    #[inline]
+    pub fn is_prefix(&self) -> bool {
+        self.prefix
+    }
+
+    /// Find the matching words, up to the limit, in trie order.
+    pub fn run(&self) -> Vec<String> {
+        let matches = SearchIter::resolved(self.trie, Cow::Borrowed(&self.allowed), Cow::Borrowed(&self.refs), self.prefix, 0);
+        match self.limit {
+            Some(limit) => matches.take(limit).collect(),
+            None => matches.collect(),
+        }
+    }
+
+    /// Test if any word matches.
+    pub fn test(&self) -> bool {
+        self.limit != Some(0)
+            && SearchIter::resolved(self.trie, Cow::Borrowed(&self.allowed), Cow::Borrowed(&self.refs), self.prefix, 0)
+                .next()
+                .is_some()
+    }
+
+    /// Count the matching words, up to the limit.
+    pub fn count(&self) -> usize {
+        let count = match self.trie.root() {
+            // Variables are only checked by walking the matches.
+            Some(_) if !self.refs.is_empty() => return self.run().len(),
+            Some(root) => self.trie.count_level(root, 0, &self.allowed, self.wild_from, self.prefix),
+            None => 0,
+        };
+        self.limit.map_or(count, |limit| count.min(limit))
+    }
+
+}
+
+
+// Compilation --------------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Compile a pattern for repeated searches. Uses '*' for wildcard
+    /// matching.
+    ///
+    /// Fails if a literal char is not in the trie's char table, since the
+    /// query could never match.
+    #[inline]
+    pub fn compile<'b>(&'b self, needle: &str) -> Result<CompiledQuery<'b>, QueryError> {
+        self.compile_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false)
+    }
+
+    /// Compile a pattern for repeated prefix searches. Uses '*' for
+    /// wildcard matching.
+    #[inline]
+    pub fn compile_pfx<'b>(&'b self, needle: &str) -> Result<CompiledQuery<'b>, QueryError> {
+        self.compile_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true)
+    }
+
+    /// Compile a per-position pattern for repeated searches.
+    ///
+    /// Fails if a position only allows chars that are not in the trie's
+    /// char table.
+    #[inline]
+    pub fn compile_pattern<'b>(&'b self, pattern: &Pattern) -> Result<CompiledQuery<'b>, QueryError> {
+        self.compile_impl(pattern, false)
+    }
+
+    /// The fully-qualified compile method.
+    fn compile_impl<'b>(&'b self, pattern: &Pattern, prefix: bool) -> Result<CompiledQuery<'b>, QueryError> {
+        for (position, set) in pattern.sets().iter().enumerate() {
+            if let CharSet::OneOf(ref chars) = *set {
+                if chars.iter().all(|&c| self.char_index(c).is_none()) {
+                    if let Some(&c) = chars.iter().next() {
+                        return Err(QueryError::UnknownChar { c, position });
+                    }
+                }
+            }
+        }
+        Ok(CompiledQuery {
+            trie: self,
+            allowed: self.resolve_pattern(pattern),
+            refs: pattern.var_refs(),
+            wild_from: wild_suffix_start(pattern),
+            prefix,
+            limit: None,
+        })
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use ::tiny_trie::fixtures::BRODA_5;
+
+    #[test]
+    fn test_compiled_query() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+
+        let query = pt.compile("ba*").unwrap();
+        assert!(!query.is_prefix());
+        assert_eq!(query.run(), vec!["bar", "baz"]);
+        assert!(query.test());
+        assert_eq!(query.count(), 2);
+
+        let query = query.with_limit(1);
+        assert_eq!(query.limit(), Some(1));
+        assert_eq!(query.run(), vec!["bar"]);
+        assert_eq!(query.count(), 1);
+        assert!(!pt.compile("ba*").unwrap().with_limit(0).test());
+
+        let query = pt.compile_pfx("f").unwrap();
+        assert_eq!(query.run(), vec!["foo"]);
+        assert_eq!(query.count(), 1);
+        assert!(!pt.compile("**").unwrap().test());
+
+        let pattern: Pattern = "[bf][ao][^r]".parse().unwrap();
+        assert_eq!(pt.compile_pattern(&pattern).unwrap().run(), vec!["foo", "baz"]);
+    }
+
+    // Literals outside the char table fail before anything is searched.
+    #[test]
+    fn test_compile_unknown_char() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.compile("b*x").err(), Some(QueryError::UnknownChar { c: 'x', position: 2 }));
+        assert_eq!(pt.compile_pfx("q").err(), Some(QueryError::UnknownChar { c: 'q', position: 0 }));
+        let pattern: Pattern = "[xy]**".parse().unwrap();
+        assert_eq!(pt.compile_pattern(&pattern).err(), Some(QueryError::UnknownChar { c: 'x', position: 0 }));
+        // Sets only fail if none of their chars are known.
+        let pattern: Pattern = "[xb]**".parse().unwrap();
+        assert_eq!(pt.compile_pattern(&pattern).unwrap().run(), vec!["bar", "baz"]);
+    }
+
+    #[test]
+    fn test_compiled_query_dist_file() {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let query = pt.compile("C*A*E").unwrap();
+        assert_eq!(query.count(), 18);
+        assert_eq!(query.run(), pt.search_iter("C*A*E").collect::<Vec<String>>());
+        assert_eq!(pt.compile("*****").unwrap().count(), 24033);
+    }
+
+    #[bench]
+    fn bench_broda_compiled_run(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let query = pt.compile("C*A*E").unwrap();
+        b.iter(|| query.run())
+    }
+
+    #[bench]
+    fn bench_broda_compiled_test(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let query = pt.compile("*R*ZE").unwrap();
+        b.iter(|| query.test())
+    }
+
+}
@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+use ::tiny_trie::packed::{wild_suffix_start, Node, PackedTrie, SearchIter, DEFAULT_WILDCARD};
+use ::tiny_trie::pattern::Pattern;
+
+
+// Word IDs -----------------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Number of words in the trie.
+    ///
+    /// Word IDs are `u32`s, so this is only meaningful for tries with at
+    /// most `u32::MAX` words. `verify` rejects larger ones.
+    pub fn word_count(&self) -> u32 {
+        self.root().map_or(0, |root| self.level_stats()[root].count as u32)
+    }
+
+    /// Find the ID of a word: its index among all words in the trie, in
+    /// lexicographic order. Returns `None` if the word is not in the trie.
+    ///
+    /// IDs run densely from 0 to `word_count() - 1`, and follow the order of
+    /// the words by char value whatever order the trie stores its levels in.
+    pub fn rank(&self, word: &str) -> Option<u32> {
+        self.rank_normalized(&self.normalize(word))
+    }
+
+    /// Find the ID of a word that is already normalized (see `rank`).
+    fn rank_normalized(&self, word: &str) -> Option<u32> {
+        let stats = self.level_stats();
+        let mut pointer = self.root()?;
+        let mut id: u64 = 0;
+        let mut chars = word.chars();
+        loop {
+            // The terminal stands in for the end of the word.
+            let wanted = match chars.next() {
+                Some(c) => self.char_index(c)?,
+                None => 0,
+            };
+            let wanted_order = self.char_order(wanted);
+            let mut found = None;
+            let mut word_ptr = pointer;
+            loop {
+                let node = self.read_node(word_ptr);
+                if node.char_idx == wanted {
+                    found = Some(node.next);
+                } else if self.char_order(node.char_idx) < wanted_order {
+                    // Skip the words below every sibling that sorts first.
+                    id += if node.char_idx == 0 { 1 } else { stats[node.next].count };
+                }
+                if node.last {
+                    break;
+                }
+                word_ptr += 1;
+            }
+            match found {
+                None => return None,
+                Some(_) if wanted == 0 => return u32::try_from(id).ok(),
+                Some(next) => pointer = next,
+            }
+        }
+    }
+
+    /// Find the word with the given ID (see `rank`).
+    ///
+    /// Panics if the ID is not less than `word_count()`.
+    pub fn unrank(&self, id: u32) -> String {
+        assert!(id < self.word_count(), "Word ID {} is out of range.", id);
+        let stats = self.level_stats();
+        let mut word = String::new();
+        let mut rest = id as u64;
+        let mut pointer = 0;
+        loop {
+            for node in self.sorted_level(pointer) {
+                let below = if node.char_idx == 0 { 1 } else { stats[node.next].count };
+                if rest >= below {
+                    rest -= below;
+                    continue;
+                }
+                if node.char_idx == 0 {
+                    return word;
+                }
+                word.push(self.char_at(node.char_idx));
+                pointer = node.next;
+                break;
+            }
+        }
+    }
+
+    /// The nodes of the level at the given pointer, sorted by their chars.
+    fn sorted_level(&self, pointer: usize) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut word_ptr = pointer;
+        loop {
+            let node = self.read_node(word_ptr);
+            let last = node.last;
+            nodes.push(node);
+            if last {
+                break;
+            }
+            word_ptr += 1;
+        }
+        nodes.sort_by_key(|node| self.char_order(node.char_idx));
+        nodes
+    }
+
+    /// Find the IDs of all words matching the given pattern, in ascending
+    /// order. Uses '*' for wildcard matching.
+    ///
+    /// IDs are cheaper to produce than words, and sets of them can be kept
+    /// as bitsets and intersected with a bitwise AND.
+    #[inline]
+    pub fn search_ids(&self, needle: &str) -> Vec<u32> {
+        self.search_ids_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), false)
+    }
+
+    /// Find the IDs of all words that use the given pattern as a prefix, in
+    /// ascending order. Uses '*' for wildcard matching.
+    #[inline]
+    pub fn search_pfx_ids(&self, needle: &str) -> Vec<u32> {
+        self.search_ids_impl(&self.needle_pattern(needle, DEFAULT_WILDCARD), true)
+    }
+
+    /// Find the IDs of all words matching the given per-position pattern,
+    /// in ascending order.
+    #[inline]
+    pub fn search_pattern_ids(&self, pattern: &Pattern) -> Vec<u32> {
+        self.search_ids_impl(pattern, false)
+    }
+
+    /// The fully-qualified ID search method.
+    fn search_ids_impl(&self, pattern: &Pattern, prefix: bool) -> Vec<u32> {
+        let root = match self.root() {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        // Subtree counts can't tell which words satisfy the variables, so
+        // those have to be walked. Matches come in trie order, so their IDs
+        // need sorting.
+        if pattern.has_vars() {
+            let mut ids: Vec<u32> = SearchIter::new(self, pattern.clone(), prefix)
+                .map(|word| self.rank_normalized(&word).unwrap())
+                .collect();
+            ids.sort_unstable();
+            return ids;
+        }
+        let mut walk = IdWalk {
+            trie: self,
+            allowed: self.resolve_pattern(pattern),
+            wild_from: wild_suffix_start(pattern),
+            prefix,
+            ids: Vec::new(),
+        };
+        walk.level(root, 0, 0);
+        walk.ids
+    }
+
+}
+
+
+/// State of a single ID search over a trie.
+struct IdWalk<'a> {
+    trie: &'a PackedTrie<'a>,
+    /// Resolved pattern (see `PackedTrie::resolve_pattern`).
+    allowed: Vec<Vec<bool>>,
+    /// Position from which the pattern is only wildcards.
+    wild_from: usize,
+    prefix: bool,
+    ids: Vec<u32>,
+}
+
+
+impl<'a> IdWalk<'a> {
+
+    /// Collect matching IDs from the level at the given pointer, whose
+    /// words have IDs starting at `first`.
+    fn level(&mut self, pointer: usize, depth: usize, first: u64) {
+        let last_depth = self.allowed.len();
+        let stats = self.trie.level_stats()[pointer];
+
+        // Once the rest of the pattern is wild, a level whose words all
+        // have the right length matches as a whole. Its IDs are contiguous.
+        if depth >= self.wild_from {
+            let remaining = last_depth - depth;
+            let min_len = stats.min_len as usize;
+            let max_len = stats.max_len as usize;
+            let (none, all) = if self.prefix {
+                (max_len < remaining, min_len >= remaining)
+            } else {
+                (remaining < min_len || remaining > max_len,
+                 min_len == remaining && max_len == remaining)
+            };
+            if stats.count == 0 || none {
+                return;
+            }
+            if all {
+                self.ids.extend((first..first + stats.count).map(|id| id as u32));
+                return;
+            }
+        }
+
+        // Children are visited in sorted order, so IDs come out ascending.
+        let mut id = first;
+        for node in self.trie.sorted_level(pointer) {
+            if node.char_idx == 0 {
+                if depth == last_depth {
+                    self.ids.push(id as u32);
+                }
+                id += 1;
+            } else {
+                if depth < last_depth && self.allowed[depth][node.char_idx as usize] {
+                    self.level(node.next, depth + 1, id);
+                }
+                id += self.trie.level_stats()[node.next].count;
+            }
+        }
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::fixtures::{BRODA_3, BRODA_5, NYT_7};
+    use ::tiny_trie::packed::PackedTrie;
+    use ::tiny_trie::pattern::{CharSet, Pattern};
+
+    #[test]
+    fn test_rank_unrank() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.word_count(), 3);
+        assert_eq!(pt.rank("bar"), Some(0));
+        assert_eq!(pt.rank("baz"), Some(1));
+        assert_eq!(pt.rank("foo"), Some(2));
+        assert_eq!(pt.rank("ba"), None);
+        assert_eq!(pt.rank("bazz"), None);
+        assert_eq!(pt.rank("qux"), None);
+        assert_eq!(pt.unrank(2), "foo");
+
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["cats", "cat", "ca", "dog", "cab"]);
+        let pt = builder.build();
+        let words: Vec<String> = (0..pt.word_count()).map(|id| pt.unrank(id)).collect();
+        assert_eq!(words, vec!["ca", "cab", "cat", "cats", "dog"]);
+        assert_eq!(pt.rank("cats"), Some(3));
+
+        assert_eq!(TrieBuilder::new().build().word_count(), 0);
+        assert_eq!(TrieBuilder::new().build().rank(""), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unrank_out_of_range() {
+        PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB").unrank(3);
+    }
+
+    // The distributed lists lay out siblings in char table order, but IDs
+    // still follow the sorted word list.
+    #[test]
+    fn test_rank_dist_file() {
+        for packed in &[BRODA_3, NYT_7] {
+            let pt = PackedTrie::from(packed.trim());
+            let mut words: Vec<String> = pt.search_pfx_iter("").collect();
+            words.sort();
+            assert_eq!(pt.word_count() as usize, words.len());
+            for (id, word) in words.iter().enumerate() {
+                assert_eq!(pt.unrank(id as u32), *word);
+                assert_eq!(pt.rank(word), Some(id as u32));
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_ids() {
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["ca", "cab", "cat", "cats", "dog"]);
+        let pt = builder.build();
+        assert_eq!(pt.search_ids("ca*"), vec![1, 2]);
+        assert_eq!(pt.search_ids("***"), vec![1, 2, 4]);
+        assert_eq!(pt.search_ids("**"), vec![0]);
+        assert_eq!(pt.search_pfx_ids("ca"), vec![0, 1, 2, 3]);
+        assert_eq!(pt.search_pfx_ids("*a*"), vec![1, 2, 3]);
+        assert_eq!(pt.search_ids("x**"), Vec::<u32>::new());
+        let vowel = Pattern::new(vec![CharSet::Any, CharSet::one_of("ao".chars()), CharSet::Any]);
+        assert_eq!(pt.search_pattern_ids(&vowel), vec![1, 2, 4]);
+        assert!(pt.search_pattern_ids(&Pattern::parse("1*1").unwrap()).is_empty());
+        assert_eq!(pt.search_pattern_ids(&Pattern::parse("12*").unwrap()), vec![1, 2, 4]);
+
+        let pt = PackedTrie::from(BRODA_5.trim());
+        for needle in &["C*A*E", "*****", "**A**", "Q****"] {
+            let mut expected: Vec<u32> = pt.search(needle).iter()
+                .map(|word| pt.rank(word).unwrap())
+                .collect();
+            expected.sort();
+            assert_eq!(pt.search_ids(needle), expected);
+        }
+    }
+
+    #[bench]
+    fn bench_broda_search_ids(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        b.iter(|| pt.search_ids("C*A*E"))
+    }
+
+    #[bench]
+    fn bench_broda_search_ids_all(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        b.iter(|| pt.search_ids("*****"))
+    }
+
+}
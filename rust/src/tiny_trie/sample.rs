@@ -0,0 +1,206 @@
+use rand::Rng;
+use rand::seq::index;
+use ::tiny_trie::packed::{wild_suffix_start, PackedTrie, SearchIter, DEFAULT_WILDCARD};
+use ::tiny_trie::pattern::Pattern;
+
+
+// Random sampling ----------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Pick up to `n` distinct words matching the pattern uniformly at
+    /// random, in random order. Uses '*' for wildcard matching.
+    ///
+    /// Matches are counted rather than enumerated, and only the branches
+    /// leading to the picked words are expanded. The result depends only
+    /// on the trie, the pattern and the RNG, so a seeded RNG gives
+    /// reproducible samples.
+    pub fn sample<R: Rng + ?Sized>(&self, needle: &str, n: usize, rng: &mut R) -> Vec<String> {
+        self.sample_pattern(&self.needle_pattern(needle, DEFAULT_WILDCARD), n, rng)
+    }
+
+    /// Pick up to `n` distinct words matching the per-position pattern
+    /// uniformly at random, in random order.
+    ///
+    /// Patterns with variables are sampled from a full walk of their
+    /// matches instead.
+    pub fn sample_pattern<R: Rng + ?Sized>(&self, pattern: &Pattern, n: usize, rng: &mut R) -> Vec<String> {
+        let root = match self.root() {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        // Subtree counts can't tell which words satisfy the variables, so
+        // those have to be walked.
+        if pattern.has_vars() {
+            let matches: Vec<String> = SearchIter::new(self, pattern.clone(), false).collect();
+            return index::sample(rng, matches.len(), n.min(matches.len()))
+                .into_iter()
+                .map(|i| matches[i].clone())
+                .collect();
+        }
+        let allowed = self.resolve_pattern(pattern);
+        let wild_from = wild_suffix_start(pattern);
+        let total = self.count_level(root, 0, &allowed, wild_from, false);
+
+        // Pick distinct ranks among the matches in trie order, then find the
+        // word at each rank. The walk needs the ranks sorted; the slots put
+        // the words back in the order the ranks were drawn.
+        let mut ranks: Vec<(usize, usize)> = index::sample(rng, total, n.min(total))
+            .into_iter()
+            .enumerate()
+            .map(|(slot, rank)| (rank, slot))
+            .collect();
+        ranks.sort_unstable();
+
+        let mut walk = SampleWalk {
+            trie: self,
+            allowed,
+            wild_from,
+            memo: String::new(),
+            picks: vec![String::new(); ranks.len()],
+        };
+        walk.level(root, 0, 0, &ranks);
+        walk.picks
+    }
+
+}
+
+
+/// State of a single sampling walk over a trie.
+struct SampleWalk<'a> {
+    trie: &'a PackedTrie<'a>,
+    /// Resolved pattern (see `PackedTrie::resolve_pattern`).
+    allowed: Vec<Vec<bool>>,
+    /// Position from which the pattern is only wildcards.
+    wild_from: usize,
+    /// Chars on the path to the current level.
+    memo: String,
+    /// Picked words, by the order their ranks were drawn.
+    picks: Vec<String>,
+}
+
+
+impl<'a> SampleWalk<'a> {
+
+    /// Find the words at the given ranks in the level at the given pointer.
+    ///
+    /// Ranks are sorted, paired with their slots, and count matches in
+    /// trie order from the start of the whole walk; the level's matches
+    /// start at `first`.
+    fn level(&mut self, pointer: usize, depth: usize, first: usize, ranks: &[(usize, usize)]) {
+        let last_depth = self.allowed.len();
+        let mut start = first;
+        let mut rest = ranks;
+        let mut word_ptr = pointer;
+        while !rest.is_empty() {
+            let word = self.trie.read_node(word_ptr);
+            let count = if word.char_idx == 0 {
+                (depth == last_depth) as usize
+            } else if depth < last_depth && self.allowed[depth][word.char_idx as usize] {
+                self.trie.count_level(word.next, depth + 1, &self.allowed, self.wild_from, false)
+            } else {
+                0
+            };
+
+            // Ranks within this node's matches.
+            let split = rest.iter().take_while(|&&(rank, _)| rank < start + count).count();
+            let (here, after) = rest.split_at(split);
+            if !here.is_empty() {
+                if word.char_idx == 0 {
+                    self.picks[here[0].1] = self.memo.clone();
+                } else {
+                    self.memo.push(self.trie.char_at(word.char_idx));
+                    self.level(word.next, depth + 1, start, here);
+                    self.memo.pop();
+                }
+            }
+            start += count;
+            rest = after;
+            word_ptr += 1;
+        }
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use test::Bencher;
+    use ::tiny_trie::fixtures::BRODA_5;
+    use ::tiny_trie::packed::PackedTrie;
+    use ::tiny_trie::pattern::Pattern;
+
+    #[test]
+    fn test_sample() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let mut rng = StdRng::seed_from_u64(16);
+
+        let mut picks = pt.sample("b**", 2, &mut rng);
+        picks.sort();
+        assert_eq!(picks, vec!["bar", "baz"]);
+        assert_eq!(pt.sample("f**", 5, &mut rng), vec!["foo"]);
+        assert_eq!(pt.sample("***", 0, &mut rng), Vec::<String>::new());
+        assert_eq!(pt.sample("x**", 3, &mut rng), Vec::<String>::new());
+        assert_eq!(pt.sample("**", 3, &mut rng), Vec::<String>::new());
+
+        let mut all = pt.sample("***", 10, &mut rng);
+        all.sort();
+        assert_eq!(all, vec!["bar", "baz", "foo"]);
+    }
+
+    // Samples only pick matches that satisfy the variables.
+    #[test]
+    fn test_sample_vars() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let mut rng = StdRng::seed_from_u64(5);
+        assert_eq!(pt.sample_pattern(&Pattern::parse("*11").unwrap(), 3, &mut rng), vec!["foo"]);
+        assert!(pt.sample_pattern(&Pattern::parse("1*1").unwrap(), 3, &mut rng).is_empty());
+        let mut picks = pt.sample_pattern(&Pattern::parse("b12").unwrap(), 5, &mut rng);
+        picks.sort();
+        assert_eq!(picks, vec!["bar", "baz"]);
+    }
+
+    // Every match is about equally likely to be picked.
+    #[test]
+    fn test_sample_uniform() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut tally: HashMap<String, usize> = HashMap::new();
+        for _ in 0..3000 {
+            for word in pt.sample("***", 1, &mut rng) {
+                *tally.entry(word).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(tally.len(), 3);
+        for (word, &count) in &tally {
+            assert!(count > 900 && count < 1100, "{} picked {} times", word, count);
+        }
+    }
+
+    // Seeded RNGs give reproducible samples from real lists.
+    #[test]
+    fn test_sample_dist_file() {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let picks = pt.sample("C*A*E", 10, &mut StdRng::seed_from_u64(42));
+        assert_eq!(picks.len(), 10);
+        assert_eq!(picks.iter().collect::<HashSet<_>>().len(), 10);
+        let matches: HashSet<String> = pt.search("C*A*E").into_iter().collect();
+        assert!(picks.iter().all(|word| matches.contains(word)));
+        assert_eq!(pt.sample("C*A*E", 10, &mut StdRng::seed_from_u64(42)), picks);
+        assert_eq!(pt.sample("C*A*E", 100, &mut StdRng::seed_from_u64(42)).len(), 18);
+    }
+
+    #[bench]
+    fn bench_broda_sample(b: &mut Bencher) {
+        let pt = PackedTrie::from(BRODA_5.trim());
+        let mut rng = StdRng::seed_from_u64(1);
+        b.iter(|| pt.sample("*R***", 20, &mut rng))
+    }
+
+}
@@ -0,0 +1,99 @@
+//! Serde support for packed tries, behind the `serde` feature.
+//!
+//! Human-readable formats like JSON store the Base64 encoding as a string.
+//! Binary formats store the raw binary encoding as bytes. Either encoding is
+//! accepted when deserializing.
+use std::fmt;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use ::tiny_trie::packed::PackedTrie;
+
+
+impl<'a> Serialize for PackedTrie<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_base64())
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+
+impl<'de> Deserialize<'de> for PackedTrie<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PackedTrie<'static>, D::Error> {
+        // Human-readable formats may hold bytes as an array of numbers, so
+        // let the input decide which encoding it is.
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PackedTrieVisitor)
+        } else {
+            deserializer.deserialize_bytes(PackedTrieVisitor)
+        }
+    }
+}
+
+
+/// Visitor loading a trie from either encoding.
+struct PackedTrieVisitor;
+
+
+impl<'de> Visitor<'de> for PackedTrieVisitor {
+    type Value = PackedTrie<'static>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a packed trie in its Base64 or raw binary encoding")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<PackedTrie<'static>, E> {
+        PackedTrie::try_from_str(v).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<PackedTrie<'static>, E> {
+        PackedTrie::from_bytes(v).map(PackedTrie::into_owned).map_err(E::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<PackedTrie<'static>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use ::tiny_trie::builder::TrieBuilder;
+
+    #[test]
+    fn test_serde_json() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        let json = serde_json::to_string(&pt).unwrap();
+        assert_eq!(json, "\"BAAAAABAwIfboarzKTbjds1FDB\"");
+        let loaded: PackedTrie = serde_json::from_str(&json).unwrap();
+        assert!(loaded.test("foo"));
+
+        let err = serde_json::from_str::<PackedTrie>("\"BAAAA\"").err().unwrap();
+        assert!(err.to_string().contains("Truncated header"), "{}", err);
+    }
+
+    #[test]
+    fn test_serde_bytes() {
+        let mut builder = TrieBuilder::new();
+        builder.insert_scored("cat", 3);
+        builder.insert_scored("cab", 9);
+        let pt = builder.build();
+        // JSON has no bytes type, so raw binary encodings arrive as arrays.
+        let json = serde_json::to_string(&pt.to_bytes()).unwrap();
+        let loaded: PackedTrie = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.score("cab"), Some(9));
+        assert_eq!(loaded.to_bytes(), pt.to_bytes());
+    }
+
+}
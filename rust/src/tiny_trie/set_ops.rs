@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use ::tiny_trie::builder::TrieBuilder;
+use ::tiny_trie::packed::PackedTrie;
+
+
+// Set operations -----------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Build a trie with every word in either trie.
+    ///
+    /// Words keep their scores; a word in both tries keeps the higher one.
+    /// The result has scores if either trie does, and this trie's normalizer.
+    pub fn union(&self, other: &PackedTrie) -> PackedTrie<'static> {
+        SetWalk::run(self, other, SetOp::Union)
+    }
+
+    /// Build a trie with the words found in both tries.
+    ///
+    /// Words keep the higher of their two scores. The result has scores if
+    /// either trie does. The result has this trie's normalizer.
+    pub fn intersection(&self, other: &PackedTrie) -> PackedTrie<'static> {
+        SetWalk::run(self, other, SetOp::Intersection)
+    }
+
+    /// Build a trie with the words in this trie that are not in the other,
+    /// e.g. to remove a ban list from a word list.
+    ///
+    /// Words keep their scores from this trie, and the result has this
+    /// trie's normalizer.
+    pub fn difference(&self, other: &PackedTrie) -> PackedTrie<'static> {
+        SetWalk::run(self, other, SetOp::Difference)
+    }
+
+}
+
+
+/// Operation combining the words of two tries.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+
+impl SetOp {
+
+    /// Whether a word (or a subtree of words) found in the given tries
+    /// belongs in the result.
+    #[inline]
+    fn keeps(self, left: bool, right: bool) -> bool {
+        match self {
+            SetOp::Union => left || right,
+            SetOp::Intersection => left && right,
+            SetOp::Difference => left && !right,
+        }
+    }
+
+}
+
+
+/// Contents of one level: the score of the word ending here, if any, and the
+/// child level under each char.
+#[derive(Default)]
+struct Level {
+    terminal: Option<u32>,
+    children: BTreeMap<char, usize>,
+}
+
+
+/// State of a simultaneous walk over two tries.
+///
+/// The tries are walked level by level in lockstep, following each char
+/// wherever either trie has it, so subtrees only one trie has are read
+/// without touching the other. Chars are matched by value, since the tries'
+/// char tables need not agree.
+struct SetWalk<'a> {
+    left: &'a PackedTrie<'a>,
+    right: &'a PackedTrie<'a>,
+    op: SetOp,
+    /// Chars on the path to the current level pair.
+    memo: String,
+    builder: TrieBuilder,
+    scored: bool,
+}
+
+
+impl<'a> SetWalk<'a> {
+
+    /// Combine the tries and pack the result.
+    fn run(left: &'a PackedTrie<'a>, right: &'a PackedTrie<'a>, op: SetOp) -> PackedTrie<'static> {
+        let scored = left.has_scores() || (op != SetOp::Difference && right.has_scores());
+        let mut walk = SetWalk {
+            left,
+            right,
+            op,
+            memo: String::new(),
+            builder: TrieBuilder::new(),
+            scored,
+        };
+        walk.level(left.root(), right.root());
+        let mut trie = walk.builder.build();
+        trie.set_normalizer(left.normalizer().cloned());
+        trie
+    }
+
+    /// Combine the level pair at the given pointers. A missing pointer
+    /// stands for a trie with no words below the current path.
+    fn level(&mut self, left: Option<usize>, right: Option<usize>) {
+        let left_level = left.map(|ptr| read_level(self.left, ptr)).unwrap_or_default();
+        let right_level = right.map(|ptr| read_level(self.right, ptr)).unwrap_or_default();
+
+        if self.op.keeps(left_level.terminal.is_some(), right_level.terminal.is_some()) {
+            let score = left_level.terminal.max(right_level.terminal).unwrap();
+            if self.scored {
+                self.builder.insert_scored(&self.memo, score);
+            } else {
+                self.builder.insert(&self.memo);
+            }
+        }
+
+        let mut left_children = left_level.children.into_iter().peekable();
+        let mut right_children = right_level.children.into_iter().peekable();
+        loop {
+            // Merge the sorted children, pairing up chars both levels have.
+            let (c, l, r) = match (left_children.peek(), right_children.peek()) {
+                (None, None) => break,
+                (Some(&(lc, _)), Some(&(rc, _))) if lc == rc => {
+                    let (_, l) = left_children.next().unwrap();
+                    let (_, r) = right_children.next().unwrap();
+                    (lc, Some(l), Some(r))
+                },
+                (Some(&(lc, _)), Some(&(rc, _))) if lc < rc => {
+                    let (_, l) = left_children.next().unwrap();
+                    (lc, Some(l), None)
+                },
+                (Some(&(lc, l)), None) => {
+                    left_children.next();
+                    (lc, Some(l), None)
+                },
+                (_, Some(&(rc, r))) => {
+                    right_children.next();
+                    (rc, None, Some(r))
+                },
+            };
+            // A subtree only one trie has is kept or skipped as a whole,
+            // e.g. ones only the right trie has are skipped in a difference.
+            if (l.is_none() || r.is_none()) && !self.op.keeps(l.is_some(), r.is_some()) {
+                continue;
+            }
+            self.memo.push(c);
+            self.level(l, r);
+            self.memo.pop();
+        }
+    }
+
+}
+
+
+/// Read the level at the given pointer.
+fn read_level(trie: &PackedTrie, ptr: usize) -> Level {
+    let mut level = Level::default();
+    let mut node_ptr = ptr;
+    loop {
+        let node = trie.read_node(node_ptr);
+        if node.char_idx == 0 {
+            level.terminal = Some(node.score);
+        } else {
+            level.children.insert(trie.char_at(node.char_idx), node.next);
+        }
+        if node.last {
+            return level;
+        }
+        node_ptr += 1;
+    }
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use test::Bencher;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::fixtures::BRODA_5;
+    use ::tiny_trie::packed::PackedTrie;
+
+    fn trie(words: &[&str]) -> PackedTrie<'static> {
+        let mut builder = TrieBuilder::new();
+        builder.extend(words.iter().cloned());
+        builder.build()
+    }
+
+    fn words(trie: &PackedTrie) -> Vec<String> {
+        trie.search_pfx_iter("").collect()
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let a = trie(&["bar", "baz", "foo", "fool", "quux"]);
+        let b = trie(&["ba", "baz", "fool", "zap"]);
+
+        assert_eq!(words(&a.union(&b)), vec!["ba", "bar", "baz", "foo", "fool", "quux", "zap"]);
+        assert_eq!(words(&a.intersection(&b)), vec!["baz", "fool"]);
+        assert_eq!(words(&a.difference(&b)), vec!["bar", "foo", "quux"]);
+        assert_eq!(words(&b.difference(&a)), vec!["ba", "zap"]);
+
+        let empty = trie(&[]);
+        assert_eq!(words(&a.union(&empty)), words(&a));
+        assert_eq!(words(&empty.union(&a)), words(&a));
+        assert!(words(&a.intersection(&empty)).is_empty());
+        assert_eq!(words(&a.difference(&empty)), words(&a));
+        assert!(words(&a.difference(&a)).is_empty());
+    }
+
+    #[test]
+    fn test_set_ops_scored() {
+        let mut builder = TrieBuilder::new();
+        builder.insert_scored("cat", 3).insert_scored("cab", 9);
+        let a = builder.build();
+        let b = trie(&["cab", "cot"]);
+
+        let union = a.union(&b);
+        assert!(union.has_scores());
+        assert_eq!(union.search_scored("c**", 0),
+                   vec![("cab".to_string(), 9), ("cat".to_string(), 3), ("cot".to_string(), 0)]);
+        assert_eq!(a.intersection(&b).search_scored("c**", 0), vec![("cab".to_string(), 9)]);
+        assert_eq!(a.difference(&b).search_scored("c**", 0), vec![("cat".to_string(), 3)]);
+        assert!(!b.difference(&a).has_scores());
+    }
+
+    #[test]
+    fn test_set_ops_dist_file() {
+        let broda = PackedTrie::from(BRODA_5.trim());
+        let banned = trie(&["CRANE", "CRATE", "ZZZZZ"]);
+        let custom = trie(&["CRWTH", "QWERT"]);
+
+        let bank = broda.difference(&banned).union(&custom);
+        assert_eq!(bank.count("*****"), 24033 - 2 + 1);
+        assert!(!bank.test("CRANE"));
+        assert!(bank.test("CRAKE"));
+        assert!(!broda.test("QWERT") && bank.test("QWERT"));
+        assert!(bank.test("CRWTH"));
+        // Dist files don't order their chars, so compare the words as sets.
+        let mut all = words(&broda);
+        all.sort();
+        assert_eq!(words(&broda.intersection(&broda)), all);
+        assert_eq!(bank.count("QW***"), broda.count("QW***") + 1);
+    }
+
+    #[bench]
+    fn bench_broda_difference(b: &mut Bencher) {
+        let broda = PackedTrie::from(BRODA_5.trim());
+        let banned = trie(&["CRANE", "CRATE", "ZZZZZ"]);
+        b.iter(|| broda.difference(&banned))
+    }
+
+}
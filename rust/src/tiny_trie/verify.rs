@@ -0,0 +1,279 @@
+//! Structural verification of packed tries loaded from untrusted input.
+use ::tiny_trie::error::VerifyError;
+use ::tiny_trie::packed::PackedTrie;
+
+
+/// Counts gathered while verifying a trie.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrieStats {
+    /// Number of distinct reachable nodes, terminals included.
+    pub nodes: usize,
+    /// Number of distinct reachable nodes that link to a child level.
+    pub edges: usize,
+    /// Number of words in the trie.
+    pub words: u64,
+}
+
+
+
+// Verification -------------------------------------------------------------
+
+impl<'a> PackedTrie<'a> {
+
+    /// Walk every reachable node and check the structure of the trie.
+    ///
+    /// Every level must lie within the body and end with a node flagged as
+    /// last, every char index must be in the char table, and no node may
+    /// point back to a level on its own path. A trie that passes can be
+    /// searched without out-of-range reads or endless loops.
+    ///
+    /// Words may be at most `u16::MAX` chars long, and there may be at most
+    /// `u32::MAX` of them, so that lengths and word IDs fit their types.
+    ///
+    /// Shared (DAWG) levels are visited once, so this takes time linear in
+    /// the size of the body.
+    pub fn verify(&self) -> Result<TrieStats, VerifyError> {
+        let node_count = self.node_count();
+        let mut check = Verifier {
+            trie: self,
+            node_count,
+            visits: vec![Visit::New; node_count],
+            seen: vec![false; node_count],
+            stats: TrieStats::default(),
+        };
+
+        let root = match self.root() {
+            Some(root) => root,
+            None => return Ok(check.stats),
+        };
+        check.enter(root)?;
+        let mut stack = vec![Frame { level: root, cursor: root, done: false, words: 0 }];
+
+        // Depth-first over levels, with an explicit stack so that a deep
+        // malformed body can't overflow the call stack.
+        while let Some(frame) = stack.last_mut() {
+            if frame.done {
+                let Frame { level, words, .. } = stack.pop().unwrap();
+                check.visits[level] = Visit::Done(words);
+                match stack.last_mut() {
+                    Some(parent) => parent.words = parent.words.saturating_add(words),
+                    None => check.stats.words = words,
+                }
+                continue;
+            }
+
+            let ptr = frame.cursor;
+            let node = self.read_node(ptr);
+            frame.cursor += 1;
+            frame.done = node.last;
+
+            if node.char_idx == 0 {
+                frame.words = frame.words.saturating_add(1);
+                continue;
+            }
+            match check.visits[node.next] {
+                Visit::Done(words) => frame.words = frame.words.saturating_add(words),
+                Visit::Open => return Err(VerifyError::Cycle { node: ptr }),
+                Visit::New => {
+                    // Words ending in the new level are as long as the
+                    // stack is deep.
+                    if stack.len() > u16::MAX as usize {
+                        return Err(VerifyError::TooDeep { level: node.next });
+                    }
+                    check.enter(node.next)?;
+                    stack.push(Frame { level: node.next, cursor: node.next, done: false, words: 0 });
+                },
+            }
+        }
+
+        if check.stats.words > u32::MAX as u64 {
+            return Err(VerifyError::TooManyWords { words: check.stats.words });
+        }
+        Ok(check.stats)
+    }
+
+}
+
+
+/// Progress of the walk through a level.
+#[derive(Clone, Copy)]
+enum Visit {
+    /// Not reached yet.
+    New,
+    /// On the current path.
+    Open,
+    /// Fully explored, with the number of words below it.
+    Done(u64),
+}
+
+
+/// Position within a level on the verification stack.
+struct Frame {
+    /// Index of the level's first node.
+    level: usize,
+    /// Index of the next node to examine in the level.
+    cursor: usize,
+    /// Whether the last node in the level has been examined.
+    done: bool,
+    /// Words found below the level so far.
+    words: u64,
+}
+
+
+/// State of a single verification pass.
+struct Verifier<'a> {
+    trie: &'a PackedTrie<'a>,
+    node_count: usize,
+    /// Progress through each level, indexed by its first node.
+    visits: Vec<Visit>,
+    /// Nodes already counted. Levels may overlap, so nodes are tracked
+    /// separately from levels.
+    seen: Vec<bool>,
+    stats: TrieStats,
+}
+
+
+impl<'a> Verifier<'a> {
+
+    /// Check every node in the level at the given pointer, and open it.
+    ///
+    /// Everything the walk later reads from the level is checked here, so
+    /// the walk itself never reads out of bounds.
+    fn enter(&mut self, level: usize) -> Result<(), VerifyError> {
+        let mut ptr = level;
+        loop {
+            if ptr >= self.node_count {
+                return Err(VerifyError::UnterminatedLevel { level });
+            }
+            let node = self.trie.read_node(ptr);
+            if node.char_idx as usize > self.trie.char_count() {
+                return Err(VerifyError::InvalidCharIndex { node: ptr, char_idx: node.char_idx });
+            }
+            // Negative targets wrap around to huge pointers; casting back
+            // recovers them for the report.
+            if node.char_idx != 0 && node.next >= self.node_count {
+                return Err(VerifyError::PointerOutOfBounds { node: ptr, pointer: node.next as i64 });
+            }
+            if !self.seen[ptr] {
+                self.seen[ptr] = true;
+                self.stats.nodes += 1;
+                if node.char_idx != 0 {
+                    self.stats.edges += 1;
+                }
+            }
+            if node.last {
+                break;
+            }
+            ptr += 1;
+        }
+        self.visits[level] = Visit::Open;
+        Ok(())
+    }
+
+}
+
+
+
+// Tests --------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tiny_trie::bit_writer::BitWriter;
+    use ::tiny_trie::builder::TrieBuilder;
+    use ::tiny_trie::constants::VERSION;
+    use ::tiny_trie::fixtures::BRODA_3;
+    use ::tiny_trie::header::Header;
+
+    /// Encode a trie by hand from (char index, pointer, last) nodes, with
+    /// 3-bit chars and 4-bit pointers.
+    fn encode(offset: i32, char_table: &str, nodes: &[(u32, u32, bool)]) -> String {
+        let header = Header {
+            version: VERSION,
+            offset,
+            char_width: 3,
+            ptr_width: 4,
+            score_width: 0,
+            char_table,
+        };
+        let mut body = BitWriter::new();
+        for &(char_idx, ptr, last) in nodes {
+            body.write(char_idx, 3);
+            body.write(ptr, 4);
+            body.write(last as u32, 1);
+        }
+        let mut encoded = header.to_base64();
+        encoded.push_str(&body.to_base64());
+        encoded
+    }
+
+    #[test]
+    fn test_verify_valid() {
+        let pt = PackedTrie::from("BAAAAABAwIfboarzKTbjds1FDB");
+        assert_eq!(pt.verify(), Ok(TrieStats { nodes: 10, edges: 7, words: 3 }));
+
+        let mut builder = TrieBuilder::new();
+        builder.extend(vec!["cat", "hat", "cats", "hats"]);
+        // Both words share the "at", "ats" suffix levels.
+        assert_eq!(builder.build().verify(), Ok(TrieStats { nodes: 7, edges: 5, words: 4 }));
+
+        assert_eq!(TrieBuilder::new().build().verify(), Ok(TrieStats::default()));
+    }
+
+    #[test]
+    fn test_verify_dist_file() {
+        let pt = PackedTrie::from(BRODA_3.trim());
+        let stats = pt.verify().unwrap();
+        assert_eq!(stats.words, pt.search("***").len() as u64);
+    }
+
+    #[test]
+    fn test_verify_hand_encoded() {
+        // "a" is at char index 1, and its level follows the root.
+        let pt = PackedTrie::from(&encode(0, "a", &[(1, 1, true), (0, 0, true)]));
+        assert_eq!(pt.verify(), Ok(TrieStats { nodes: 2, edges: 1, words: 1 }));
+        assert!(pt.test("a"));
+    }
+
+    #[test]
+    fn test_verify_errors() {
+        let pt = PackedTrie::from(&encode(0, "a", &[(1, 15, true)]));
+        assert_eq!(pt.verify(), Err(VerifyError::PointerOutOfBounds { node: 0, pointer: 15 }));
+
+        let pt = PackedTrie::from(&encode(-3, "a", &[(1, 0, true)]));
+        assert_eq!(pt.verify(), Err(VerifyError::PointerOutOfBounds { node: 0, pointer: -3 }));
+
+        let pt = PackedTrie::from(&encode(0, "a", &[(0, 0, false)]));
+        assert_eq!(pt.verify(), Err(VerifyError::UnterminatedLevel { level: 0 }));
+
+        let pt = PackedTrie::from(&encode(0, "a", &[(1, 1, true), (5, 0, true)]));
+        assert_eq!(pt.verify(), Err(VerifyError::InvalidCharIndex { node: 1, char_idx: 5 }));
+
+        let pt = PackedTrie::from(&encode(0, "ab", &[(1, 1, true), (0, 0, false), (2, 0, true)]));
+        assert_eq!(pt.verify(), Err(VerifyError::Cycle { node: 2 }));
+    }
+
+    #[test]
+    fn test_verify_limits() {
+        // 32 levels of "a" or "b" spell 2^32 words. Pointers are relative.
+        let mut nodes = Vec::new();
+        for _ in 0..32 {
+            nodes.push((1, 2, false));
+            nodes.push((2, 1, true));
+        }
+        nodes.push((0, 0, true));
+        let pt = PackedTrie::from(&encode(0, "ab", &nodes));
+        assert_eq!(pt.verify(), Err(VerifyError::TooManyWords { words: 1 << 32 }));
+        assert_eq!(pt.count(&"*".repeat(32)), 1 << 32);
+
+        // A single word of 2^16 "a"s.
+        let len = u16::MAX as usize + 1;
+        let mut nodes = vec![(1, 1, true); len];
+        nodes.push((0, 0, true));
+        let pt = PackedTrie::from(&encode(0, "a", &nodes));
+        assert_eq!(pt.verify(), Err(VerifyError::TooDeep { level: len }));
+        // Level statistics are gathered without recursing level by level.
+        assert_eq!(pt.count(""), 0);
+    }
+
+}